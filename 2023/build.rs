@@ -0,0 +1,65 @@
+//! Generates one test per input file listed in the `NN/answers` sidecar files,
+//! so recording the answers of a new example needs no new Rust code.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+
+fn main() {
+    // Inputs live next to the manifest unless AOC_INPUT_DIR points somewhere else, as in the runner.
+    let input_dir = std::env::var("AOC_INPUT_DIR")
+        .unwrap_or_else(|_| std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+
+    // New modules mean new days, which may come with new directories of inputs.
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-env-changed=AOC_INPUT_DIR");
+
+    let mut day_dirs: Vec<_> = std::fs::read_dir(&input_dir)
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|name| name.len() == 2 && name.parse::<u8>().is_ok())
+        .collect();
+    day_dirs.sort();
+
+    let mut tests = String::new();
+    let mut names = HashSet::new();
+    for dir in day_dirs {
+        let day_dir = Path::new(&input_dir).join(&dir);
+        println!("cargo:rerun-if-changed={}", day_dir.display());
+        let answers_path = day_dir.join("answers");
+        let Ok(answers) = std::fs::read_to_string(&answers_path) else {
+            continue;
+        };
+
+        let day: u8 = dir.parse().unwrap();
+        for line in answers.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let file = line.split_whitespace().next().unwrap();
+            let name: String = file
+                .chars()
+                .map(|c| match c.is_ascii_alphanumeric() {
+                    true => c.to_ascii_lowercase(),
+                    false => '_',
+                })
+                .collect();
+            // A file recorded twice is reported when the answers are loaded, it only needs one test.
+            if !names.insert(format!("{}_{}", day, name)) {
+                continue;
+            }
+            writeln!(
+                tests,
+                "#[test]\nfn day{:02}_{}() {{\n    check({}, {:?});\n}}\n",
+                day, name, day, file
+            )
+            .unwrap();
+        }
+    }
+
+    std::fs::write(Path::new(&out_dir).join("answer_tests.rs"), tests).unwrap();
+}
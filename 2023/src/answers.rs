@@ -0,0 +1,327 @@
+use crate::parse::{Line, Lines, ParseError};
+use crate::puzzle::{Answer, Part};
+use crate::registry::Day;
+use crate::runner::{self, Run};
+use std::collections::BTreeMap;
+use std::error;
+
+/// The name of the sidecar file in each day's directory recording the expected answers for its inputs.
+pub const FILE_NAME: &str = "answers";
+
+/// The expected answers of one input file. A part is None when its answer isn't known.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Expected {
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+}
+
+impl Expected {
+    pub fn part(&self, part: Part) -> Option<&str> {
+        match part {
+            Part::One => self.part1.as_deref(),
+            Part::Two => self.part2.as_deref(),
+        }
+    }
+}
+
+/// The expected answers for every input file of one day, read from a file like:
+///
+/// ```text
+/// # file  part1                          part2
+/// 01      142                            142
+/// 02      "error: line 2 has no digit"   281
+/// input   55488                          -
+/// ```
+///
+/// Blank lines and lines starting with '#' are ignored, and '-' marks an unknown answer.
+/// An answer holding spaces goes in double quotes, as does a part expected to fail, written "error: <message>".
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers {
+    files: BTreeMap<String, Expected>,
+}
+
+impl Answers {
+    pub fn parse(day: u8, text: &str) -> Result<Self, ParseError> {
+        let mut files = BTreeMap::new();
+        for line in Lines::new(day, text) {
+            let trimmed = line.text().trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let [file, part1, part2] = fields(&line, trimmed)?[..] else {
+                return Err(line.error(
+                    trimmed,
+                    "expected an input file name followed by the answers to part 1 and part 2",
+                ));
+            };
+            let known = |answer: &str| (answer != "-").then(|| String::from(answer));
+            let expected = Expected {
+                part1: known(part1),
+                part2: known(part2),
+            };
+            if files.insert(String::from(file), expected).is_some() {
+                return Err(
+                    line.error(file, format!("answers for '{}' are already recorded", file))
+                );
+            }
+        }
+        Ok(Answers { files })
+    }
+
+    /// Loads the answers sidecar file of a day. A day without one has no answers recorded.
+    pub fn load(day: &Day) -> Result<Self, Box<dyn error::Error>> {
+        let path = day.input_path(FILE_NAME);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Ok(Answers::parse(day.number, &text)
+                .map_err(|e| e.diagnostic(&path.display().to_string(), &text))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn get(&self, file: &str) -> Option<&Expected> {
+        self.files.get(file)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &String> {
+        self.files.keys()
+    }
+}
+
+/// Splits a line of the answers file at whitespace, keeping a field in double quotes whole, without its quotes.
+fn fields<'a>(line: &Line<'a>, text: &'a str) -> Result<Vec<&'a str>, ParseError> {
+    let mut fields = vec![];
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let end = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let Some(close) = quoted.find('"') else {
+                    return Err(line.error(rest, "expected a closing '\"'"));
+                };
+                fields.push(&quoted[..close]);
+                close + 2
+            }
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                fields.push(&rest[..end]);
+                end
+            }
+        };
+        let after = &rest[end..];
+        if !after.is_empty() && !after.starts_with(char::is_whitespace) {
+            return Err(line.error(after, "expected a space after the quoted answer"));
+        }
+        rest = after.trim_start();
+    }
+    Ok(fields)
+}
+
+/// How a part's answer compares with the recorded one.
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail {
+        expected: String,
+        actual: String,
+    },
+    /// No answer is recorded, so the actual answer can't be checked.
+    Missing {
+        actual: String,
+    },
+}
+
+impl Verdict {
+    pub fn new(expected: Option<&str>, actual: Option<&Result<Answer, String>>) -> Self {
+        let actual = match actual {
+            Some(Ok(answer)) => answer.to_string(),
+            Some(Err(e)) => format!("error: {}", e),
+            None => String::from("not run"),
+        };
+        match expected {
+            Some(expected) if expected == actual => Verdict::Pass,
+            Some(expected) => Verdict::Fail {
+                expected: String::from(expected),
+                actual,
+            },
+            None => Verdict::Missing { actual },
+        }
+    }
+
+    /// Judges both parts of a run, treating an input which could not be parsed as failing every recorded part.
+    pub fn of_run(run: &Run, expected: &Expected) -> [Verdict; 2] {
+        [Part::One, Part::Two].map(|part| match &run.parse {
+            Ok(_) => Verdict::new(expected.part(part), run.answer(part)),
+            Err(e) => {
+                let failure = Err(e.clone());
+                Verdict::new(expected.part(part), Some(&failure))
+            }
+        })
+    }
+}
+
+/// One checked (day, input file) pair.
+pub struct Check {
+    pub day: u8,
+    pub file: String,
+    pub verdicts: [Verdict; 2],
+}
+
+/// Runs every day against every input file in its directory, comparing the answers with the recorded ones.
+pub fn verify(days: &[&Day]) -> Result<Vec<Check>, Box<dyn error::Error>> {
+    let mut checks = vec![];
+    for day in days {
+        let answers = Answers::load(day)?;
+        let mut files = day.input_files();
+        files.extend(
+            answers
+                .files()
+                .filter(|f| !files.contains(f))
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+        files.sort();
+
+        for file in files {
+            let run = runner::quiet_panics(|| runner::run_day(day, &file, &[Part::One, Part::Two]));
+            let expected = answers.get(&file).cloned().unwrap_or_default();
+            checks.push(Check {
+                day: day.number,
+                verdicts: Verdict::of_run(&run, &expected),
+                file,
+            });
+        }
+    }
+    Ok(checks)
+}
+
+fn verdict_cell(verdict: &Verdict) -> String {
+    match verdict {
+        Verdict::Pass => String::from("pass"),
+        Verdict::Fail { expected, actual } => format!(
+            "FAIL: expected {}, got {}",
+            expected,
+            actual.replace('\n', " ")
+        ),
+        Verdict::Missing { actual } => format!("missing, got {}", actual.replace('\n', " ")),
+    }
+}
+
+/// Prints one row per checked input and a count of each verdict.
+pub fn print_checks(checks: &[Check]) {
+    let rows: Vec<Vec<String>> = checks
+        .iter()
+        .map(|c| {
+            vec![
+                format!("{:02}", c.day),
+                c.file.clone(),
+                verdict_cell(&c.verdicts[0]),
+                verdict_cell(&c.verdicts[1]),
+            ]
+        })
+        .collect();
+    runner::print_columns(&["Day", "Input", "Part 1", "Part 2"], &rows);
+
+    let verdicts = || checks.iter().flat_map(|c| c.verdicts.iter());
+    println!(
+        "\n{} passed, {} failed, {} missing",
+        verdicts().filter(|v| **v == Verdict::Pass).count(),
+        verdicts()
+            .filter(|v| matches!(v, Verdict::Fail { .. }))
+            .count(),
+        verdicts()
+            .filter(|v| matches!(v, Verdict::Missing { .. }))
+            .count(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks every recorded answer of one input file. The generated tests below call this for each answers file entry.
+    fn check(day: u8, file: &str) {
+        let day = crate::registry::find(crate::DAYS, &day.to_string()).unwrap();
+        let expected = Answers::load(day).unwrap().get(file).cloned().unwrap();
+        let run = runner::run_day(day, file, &[Part::One, Part::Two]);
+        for (part, verdict) in [Part::One, Part::Two]
+            .iter()
+            .zip(Verdict::of_run(&run, &expected))
+        {
+            if let Verdict::Fail { expected, actual } = verdict {
+                panic!(
+                    "day {:02} {} {}: expected {}, got {}",
+                    day.number, file, part, expected, actual
+                );
+            }
+        }
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/answer_tests.rs"));
+
+    #[test]
+    fn parse_answers() {
+        let answers =
+            Answers::parse(1, "# file part1 part2\n01 142 142\n\ninput 55488 -\n").unwrap();
+        assert_eq!(
+            answers.get("input"),
+            Some(&Expected {
+                part1: Some(String::from("55488")),
+                part2: None,
+            })
+        );
+        assert_eq!(answers.files().collect::<Vec<_>>(), vec!["01", "input"]);
+    }
+
+    #[test]
+    fn parse_quoted_answers() {
+        let answers = Answers::parse(1, "02  \"error: line 2 has no digit\"  281\n").unwrap();
+        assert_eq!(
+            answers.get("02"),
+            Some(&Expected {
+                part1: Some(String::from("error: line 2 has no digit")),
+                part2: Some(String::from("281")),
+            })
+        );
+        let error = Answers::parse(1, "02 \"error: no digit 281\n").unwrap_err();
+        assert_eq!(
+            (error.column, error.message.as_str()),
+            (4, "expected a closing '\"'")
+        );
+        let error = Answers::parse(1, "02 \"error\"281 -\n").unwrap_err();
+        assert_eq!(error.column, 11);
+    }
+
+    #[test]
+    fn parse_bad_answers() {
+        let error = Answers::parse(1, "01 142 142\n02 209\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        let error = Answers::parse(1, "01 142 142\n01 209 281\n").unwrap_err();
+        assert_eq!(error.message, "answers for '01' are already recorded");
+    }
+
+    #[test]
+    fn verdicts() {
+        let answer = Some(&Ok(Answer::Integer(142)));
+        assert_eq!(Verdict::new(Some("142"), answer), Verdict::Pass);
+        assert_eq!(
+            Verdict::new(Some("143"), answer),
+            Verdict::Fail {
+                expected: String::from("143"),
+                actual: String::from("142"),
+            }
+        );
+        assert_eq!(
+            Verdict::new(None, answer),
+            Verdict::Missing {
+                actual: String::from("142"),
+            }
+        );
+        assert_eq!(
+            Verdict::new(Some("142"), Some(&Err(String::from("panicked")))),
+            Verdict::Fail {
+                expected: String::from("142"),
+                actual: String::from("error: panicked"),
+            }
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use crate::puzzle::Part;
+use crate::registry::Day;
+use crate::report::json_string;
+use std::error;
+use std::time::{Duration, Instant};
+
+/// Summary statistics of repeated timings.
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    pub fn new(samples: &[Duration]) -> Self {
+        assert!(!samples.is_empty());
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+
+        let n = sorted.len();
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        } else {
+            sorted[n / 2]
+        };
+
+        let nanos: Vec<f64> = sorted.iter().map(|d| d.as_nanos() as f64).collect();
+        let mean = nanos.iter().sum::<f64>() / n as f64;
+        // The sample standard deviation, a single run has none.
+        let variance = match n {
+            1 => 0.0,
+            _ => nanos.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64,
+        };
+
+        Stats {
+            min: sorted[0],
+            median,
+            mean: Duration::from_nanos(mean.round() as u64),
+            stddev: Duration::from_nanos(variance.sqrt().round() as u64),
+        }
+    }
+}
+
+/// Timings of one phase of solving: parsing the input or solving a part.
+pub struct Phase {
+    pub name: String,
+    pub stats: Stats,
+}
+
+pub struct Bench {
+    pub day: u8,
+    pub input: String,
+    pub warmup: usize,
+    pub runs: usize,
+    pub phases: Vec<Phase>,
+}
+
+/// Times parsing and each part separately, discarding the warm-up rounds.
+/// A fresh solver is built every round, so no part can benefit from work done by an earlier one.
+pub fn bench(
+    day: &Day,
+    input_name: &str,
+    input: &str,
+    warmup: usize,
+    runs: usize,
+) -> Result<Bench, Box<dyn error::Error>> {
+    if runs == 0 {
+        return Err("The number of runs must be at least 1.".into());
+    }
+
+    let parts = [Part::One, Part::Two];
+    let mut parse_samples = vec![];
+    let mut part_samples = vec![vec![]; parts.len()];
+
+    for round in 0..warmup + runs {
+        let start = Instant::now();
+        let solver = (day.build)(input)?;
+        let parse_time = start.elapsed();
+
+        let mut part_times = vec![];
+        for part in parts {
+            let start = Instant::now();
+            solver.solve_part(part)?;
+            part_times.push(start.elapsed());
+        }
+
+        if round >= warmup {
+            parse_samples.push(parse_time);
+            for (samples, time) in part_samples.iter_mut().zip(part_times) {
+                samples.push(time);
+            }
+        }
+    }
+
+    let mut phases = vec![Phase {
+        name: String::from("parse"),
+        stats: Stats::new(&parse_samples),
+    }];
+    for (part, samples) in parts.iter().zip(part_samples) {
+        phases.push(Phase {
+            name: part.to_string(),
+            stats: Stats::new(&samples),
+        });
+    }
+
+    Ok(Bench {
+        day: day.number,
+        input: String::from(input_name),
+        warmup,
+        runs,
+        phases,
+    })
+}
+
+impl Bench {
+    pub fn print(&self) {
+        println!(
+            "day {:02}, input {}, {} warm-up and {} timed runs",
+            self.day, self.input, self.warmup, self.runs
+        );
+        println!(
+            "{:<6} {:>12} {:>12} {:>12} {:>12}",
+            "phase", "min", "median", "mean", "stddev"
+        );
+        for phase in &self.phases {
+            println!(
+                "{:<6} {:>12} {:>12} {:>12} {:>12}",
+                phase.name,
+                format!("{:.2?}", phase.stats.min),
+                format!("{:.2?}", phase.stats.median),
+                format!("{:.2?}", phase.stats.mean),
+                format!("{:.2?}", phase.stats.stddev),
+            );
+        }
+    }
+
+    /// Renders the results as JSON, with times in nanoseconds, so runs can be compared between commits.
+    pub fn to_json(&self) -> String {
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|p| {
+                format!(
+                    "{{\"phase\":{},\"min_ns\":{},\"median_ns\":{},\"mean_ns\":{},\"stddev_ns\":{}}}",
+                    json_string(&p.name),
+                    p.stats.min.as_nanos(),
+                    p.stats.median.as_nanos(),
+                    p.stats.mean.as_nanos(),
+                    p.stats.stddev.as_nanos()
+                )
+            })
+            .collect();
+        format!(
+            "{{\"day\":{},\"input\":{},\"warmup\":{},\"runs\":{},\"phases\":[{}]}}",
+            self.day,
+            json_string(&self.input),
+            self.warmup,
+            self.runs,
+            phases.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn stats_odd() {
+        assert_eq!(
+            Stats::new(&[ms(4), ms(2), ms(9)]),
+            Stats {
+                min: ms(2),
+                median: ms(4),
+                mean: ms(5),
+                stddev: Duration::from_nanos(3_605_551),
+            }
+        );
+    }
+
+    #[test]
+    fn stats_even() {
+        let stats = Stats::new(&[ms(1), ms(3), ms(5), ms(7)]);
+        assert_eq!(stats.median, ms(4));
+        assert_eq!(stats.mean, ms(4));
+    }
+
+    #[test]
+    fn stats_single() {
+        let stats = Stats::new(&[ms(3)]);
+        assert_eq!(
+            (stats.min, stats.median, stats.stddev),
+            (ms(3), ms(3), ms(0))
+        );
+    }
+
+    #[test]
+    fn bench_json() {
+        let bench = bench(&crate::day06::DAY, "01", "Time: 7\nDistance: 9", 1, 3).unwrap();
+        let json = bench.to_json();
+        assert!(
+            json.starts_with("{\"day\":6,\"input\":\"01\",\"warmup\":1,\"runs\":3,\"phases\":[")
+        );
+        assert!(json.contains("{\"phase\":\"parse\",\"min_ns\":"));
+        assert!(json.contains("{\"phase\":\"part2\",\"min_ns\":"));
+    }
+}
@@ -1,51 +1,180 @@
+use crate::parse::ParseError;
 use crate::puzzle;
+use crate::registry;
 
-pub struct Day01 {
-    input: String,
+/// Some text which stands for a digit in a calibration line.
+#[derive(Clone, Debug, PartialEq)]
+struct Token {
+    text: String,
+    digit: i64,
 }
 
-fn get_calibration(line: &str) -> i64 {
-    let mut filter = line.chars().filter(char::is_ascii_digit);
-    // Some example inputs aren't valid for part 1 rules, in these cases we'll just return 0.
-    let first = filter.next().unwrap_or('0');
-    let last = filter.last().unwrap_or(first);
-    let first_byte = u8::try_from(first).unwrap();
-    let last_byte = u8::try_from(last).unwrap();
-    return i64::from((first_byte - b'0') * 10 + (last_byte - b'0'));
+fn tokens(table: &[(&str, i64)]) -> Vec<Token> {
+    table
+        .iter()
+        .map(|&(text, digit)| Token {
+            text: String::from(text),
+            digit,
+        })
+        .collect()
 }
 
-impl Day01 {
-    fn solve_p1(&self) -> i64 {
-        self.input.lines().map(get_calibration).sum()
+fn digits() -> Vec<Token> {
+    tokens(&[
+        ("0", 0),
+        ("1", 1),
+        ("2", 2),
+        ("3", 3),
+        ("4", 4),
+        ("5", 5),
+        ("6", 6),
+        ("7", 7),
+        ("8", 8),
+        ("9", 9),
+    ])
+}
+
+/// Reads the words part 2 counts as digits: "english", "roman", or a list like "un:1,deux:2".
+fn words(table: &str) -> Result<Vec<Token>, String> {
+    match table {
+        "english" => Ok(tokens(&[
+            ("one", 1),
+            ("two", 2),
+            ("three", 3),
+            ("four", 4),
+            ("five", 5),
+            ("six", 6),
+            ("seven", 7),
+            ("eight", 8),
+            ("nine", 9),
+        ])),
+        "roman" => Ok(tokens(&[
+            ("I", 1),
+            ("II", 2),
+            ("III", 3),
+            ("IV", 4),
+            ("V", 5),
+            ("VI", 6),
+            ("VII", 7),
+            ("VIII", 8),
+            ("IX", 9),
+        ])),
+        _ => table
+            .split(',')
+            .map(|entry| match entry.split_once(':') {
+                Some((text, digit)) if !text.is_empty() => match digit.parse::<i64>() {
+                    Ok(digit @ 0..=9) => Ok(Token {
+                        text: String::from(text),
+                        digit,
+                    }),
+                    _ => Err(format!("Invalid digit '{}' for '{}'.", digit, text)),
+                },
+                _ => Err(format!(
+                    "Invalid word '{}', expected english, roman, or word:digit pairs.",
+                    entry
+                )),
+            })
+            .collect(),
     }
+}
+
+/// The digit of the longest token starting at the given byte of the line.
+fn token_at(line: &[u8], start: usize, tokens: &[Token]) -> Option<i64> {
+    tokens
+        .iter()
+        .filter(|token| line[start..].starts_with(token.text.as_bytes()))
+        .max_by_key(|token| token.text.len())
+        .map(|token| token.digit)
+}
 
-    fn solve_p2(&self) -> i64 {
-        self.input
-            // Note the strange replace strings. This is to account for partial overlaps in the input data.
-            .replace("one", "o1e") // twone, oneight
-            .replace("two", "t2o") // eightwo, twone
-            .replace("three", "t3e") // eighthree, threeight
-            .replace("four", "4")
-            .replace("five", "5e") // fiveight
-            .replace("six", "6")
-            .replace("seven", "7n") // sevenine
-            .replace("eight", "e8t") // threeight, eightwo, eighthree
-            .replace("nine", "n9e") // sevenine, nineight
-            .lines()
-            .map(get_calibration)
-            .sum()
+/// The digit of the longest token ending just before the given byte of the line.
+fn token_ending_at(line: &[u8], end: usize, tokens: &[Token]) -> Option<i64> {
+    tokens
+        .iter()
+        .filter(|token| line[..end].ends_with(token.text.as_bytes()))
+        .max_by_key(|token| token.text.len())
+        .map(|token| token.digit)
+}
+
+/// Scans in from both ends of the line, for the first token by where it starts and the last by where it ends,
+/// so tokens sharing letters like "eightwo" both count. None when the line has no token.
+fn get_calibration(line: &str, tokens: &[Token]) -> Option<i64> {
+    let bytes = line.as_bytes();
+    let first = (0..bytes.len()).find_map(|i| token_at(bytes, i, tokens))?;
+    let last = (1..=bytes.len())
+        .rev()
+        .find_map(|end| token_ending_at(bytes, end, tokens))?;
+    Some(first * 10 + last)
+}
+
+pub struct Day01 {
+    input: String,
+    part1_tokens: Vec<Token>,
+    /// The digits along with the words for them.
+    part2_tokens: Vec<Token>,
+}
+
+impl Day01 {
+    fn sum_calibrations(&self, tokens: &[Token]) -> Result<i64, Box<dyn std::error::Error>> {
+        let mut sum = 0;
+        let mut missing = vec![];
+        for (i, line) in self.input.lines().enumerate() {
+            match get_calibration(line, tokens) {
+                Some(calibration) => sum += calibration,
+                None => missing.push((i + 1).to_string()),
+            }
+        }
+        match missing.len() {
+            0 => Ok(sum),
+            1 => Err(format!("line {} has no digit", missing[0]).into()),
+            _ => Err(format!("lines {} have no digit", missing.join(", ")).into()),
+        }
     }
 }
 
-impl From<String> for Day01 {
-    fn from(input: String) -> Self {
-        Day01 { input }
+pub const DAY: registry::Day = registry::Day {
+    number: 1,
+    title: "Trebuchet?!",
+    build: |input| Ok(Box::new(Day01::try_from(input)?)),
+};
+
+impl TryFrom<&str> for Day01 {
+    type Error = ParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let mut part2_tokens = digits();
+        part2_tokens.extend(words("english").unwrap());
+        Ok(Day01 {
+            input: String::from(input),
+            part1_tokens: digits(),
+            part2_tokens,
+        })
     }
 }
 
 impl puzzle::Solve for Day01 {
-    fn solve(&mut self) -> Result<puzzle::Solution, Box<dyn std::error::Error>> {
-        Ok(puzzle::Solution::Integer(self.solve_p1(), self.solve_p2()))
+    fn solve_p1(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        Ok(puzzle::Answer::Integer(
+            self.sum_calibrations(&self.part1_tokens)?,
+        ))
+    }
+
+    fn solve_p2(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        Ok(puzzle::Answer::Integer(
+            self.sum_calibrations(&self.part2_tokens)?,
+        ))
+    }
+
+    /// `words=roman` or `words=un:1,deux:2` changes which words part 2 reads as digits.
+    fn configure(&mut self, setting: &puzzle::Setting) -> Result<(), Box<dyn std::error::Error>> {
+        match setting.key.as_str() {
+            "words" => {
+                self.part2_tokens = digits();
+                self.part2_tokens.extend(words(&setting.value)?);
+            }
+            _ => return Err(format!("Unknown setting '{}'.", setting.key).into()),
+        }
+        Ok(())
     }
 }
 
@@ -55,31 +184,62 @@ mod tests {
     use crate::puzzle::Solve;
 
     #[test]
-    fn file_01() {
-        let mut solver: Day01 = std::fs::read_to_string("01/01").unwrap().into();
-        assert_eq!(solver.solve().unwrap(), crate::Solution::Integer(142, 142));
+    fn test_get_calibration() {
+        let digits = digits();
+        assert_eq!(get_calibration("1abc2", &digits), Some(12));
+        assert_eq!(get_calibration("pqr3stu8vwx", &digits), Some(38));
+        assert_eq!(get_calibration("a1b2c3d4e5f", &digits), Some(15));
+        assert_eq!(get_calibration("treb7uchet", &digits), Some(77));
+        assert_eq!(get_calibration("trebuchet", &digits), None);
     }
 
     #[test]
-    fn file_02() {
-        let mut solver: Day01 = std::fs::read_to_string("01/02").unwrap().into();
-        assert_eq!(solver.solve().unwrap(), crate::Solution::Integer(209, 281));
+    fn overlapping_words() {
+        let day = Day01::try_from("").unwrap();
+        let tokens = &day.part2_tokens;
+        assert_eq!(get_calibration("eightwo", tokens), Some(82));
+        assert_eq!(get_calibration("oneight", tokens), Some(18));
+        assert_eq!(get_calibration("twone", tokens), Some(21));
+        assert_eq!(get_calibration("sevenine", tokens), Some(79));
+        assert_eq!(get_calibration("xtwonex", tokens), Some(21));
+        assert_eq!(get_calibration("7pqrstsixteen", tokens), Some(76));
     }
 
     #[test]
-    fn file_input() {
-        let mut solver: Day01 = std::fs::read_to_string("01/input").unwrap().into();
+    fn lines_without_digits_are_reported() {
+        let day = Day01::try_from("two1nine\neightwothree\nabc\n7pqrst").unwrap();
+        assert_eq!(
+            day.solve_p1().unwrap_err().to_string(),
+            "lines 2, 3 have no digit"
+        );
         assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(55488, 55614)
+            day.solve_p2().unwrap_err().to_string(),
+            "line 3 has no digit"
         );
     }
 
     #[test]
-    fn test_get_calibration() {
-        assert_eq!(get_calibration("1abc2"), 12);
-        assert_eq!(get_calibration("pqr3stu8vwx"), 38);
-        assert_eq!(get_calibration("a1b2c3d4e5f"), 15);
-        assert_eq!(get_calibration("treb7uchet"), 77);
+    fn configure_words() {
+        let mut day = Day01::try_from("xIVx\nVIII\nIXI\nvier").unwrap();
+        day.configure(&puzzle::Setting::try_from("words=roman").unwrap())
+            .unwrap();
+        assert_eq!(
+            day.solve_p2().unwrap_err().to_string(),
+            "line 4 has no digit"
+        );
+        assert_eq!(get_calibration("xIVx", &day.part2_tokens), Some(44));
+        assert_eq!(get_calibration("VIII", &day.part2_tokens), Some(88));
+
+        day.configure(&puzzle::Setting::try_from("words=vier:4,I:1,VIII:8").unwrap())
+            .unwrap();
+        assert_eq!(
+            day.solve_p2().unwrap(),
+            puzzle::Answer::Integer(11 + 88 + 11 + 44)
+        );
+
+        for words in ["words=", "words=eins", "words=zehn:10"] {
+            let setting = puzzle::Setting::try_from(words).unwrap();
+            assert!(day.configure(&setting).is_err(), "{}", words);
+        }
     }
 }
@@ -1,108 +1,386 @@
+use crate::parse::{Line, Lines, ParseError};
 use crate::puzzle;
+use crate::registry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 
-struct Sample {
-    red: u8,
-    green: u8,
-    blue: u8,
-}
+/// Some cubes by color, like a handful shown from the bag, or the bag itself.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Cubes(BTreeMap<String, u64>);
+
+impl Cubes {
+    /// Reads a list like "3 blue, 4 red".
+    fn parse<'a>(line: &Line<'a>, text: &'a str) -> Result<Self, ParseError> {
+        let mut cubes = Cubes::default();
+        for s in text.split(",") {
+            let (number, color) = line.split_once(s.trim(), " ")?;
+            let number = line.parse::<u64>(number)?;
+            if color.is_empty() || !color.chars().all(char::is_alphabetic) {
+                return Err(line.error(color, format!("invalid color '{}'", color)));
+            }
+            if cubes.0.insert(String::from(color), number).is_some() {
+                return Err(line.error(color, format!("'{}' is already counted", color)));
+            }
+        }
+        Ok(cubes)
+    }
+
+    /// Reads cubes given outside the puzzle input, like a bag on the command line.
+    fn parse_text(text: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let line = Lines::new(2, text).next().ok_or("No cubes given.")?;
+        Cubes::parse(&line, line.text())
+            .map_err(|e| format!("Invalid cubes '{}': {}.", text, e.message).into())
+    }
+
+    fn from_counts(colors: &[&str], counts: &[u64]) -> Self {
+        let counts = colors.iter().zip(counts);
+        Cubes(
+            counts
+                .map(|(color, &n)| (String::from(*color), n))
+                .collect(),
+        )
+    }
+
+    fn count(&self, color: &str) -> u64 {
+        self.0.get(color).copied().unwrap_or(0)
+    }
+
+    fn total(&self) -> u128 {
+        self.0.values().map(|&n| u128::from(n)).sum()
+    }
 
-fn is_sample_valid_part1(s: &Sample) -> bool {
-    s.red <= 12 && s.green <= 13 && s.blue <= 14
+    /// Whether there are at least as many cubes of every color as in the other cubes.
+    fn covers(&self, other: &Cubes) -> bool {
+        other.0.iter().all(|(color, &n)| self.count(color) >= n)
+    }
 }
 
-impl From<&str> for Sample {
-    fn from(sample_text: &str) -> Self {
-        let mut sample = Sample {
-            red: 0,
-            green: 0,
-            blue: 0,
-        };
-        for s in sample_text.split(",") {
-            let (number, color) = s.trim().split_once(" ").unwrap();
-            let parsed_number = number.parse::<u8>().unwrap();
-            match color {
-                "red" => sample.red = parsed_number,
-                "green" => sample.green = parsed_number,
-                "blue" => sample.blue = parsed_number,
-                _ => panic!("Invalid color: {}", color),
-            }
+impl fmt::Display for Cubes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "no cubes");
         }
-        return sample;
+        let counts: Vec<String> = self
+            .0
+            .iter()
+            .map(|(color, n)| format!("{} {}", n, color))
+            .collect();
+        write!(f, "{}", counts.join(", "))
     }
 }
 
+#[derive(Debug)]
 struct Game {
     id: i64,
-    samples: Vec<Sample>,
+    samples: Vec<Cubes>,
 }
 
-struct GameSoA {
-    red: Vec<i64>,
-    green: Vec<i64>,
-    blue: Vec<i64>,
-}
+impl Game {
+    fn parse(line: Line) -> Result<Self, ParseError> {
+        // Game 1: 3 blue, 4 red;
+        // prefix: suffix
+        let (prefix, suffix) = line.split_once(line.text(), ":")?;
+        Ok(Game {
+            id: line.parse::<i64>(line.strip_prefix(prefix, "Game ")?)?,
+            samples: suffix
+                .split(";")
+                .map(|s| Cubes::parse(&line, s))
+                .collect::<Result<_, _>>()?,
+        })
+    }
 
-impl From<&Game> for GameSoA {
-    fn from(game: &Game) -> Self {
-        GameSoA {
-            red: game.samples.iter().map(|s| i64::from(s.red)).collect(),
-            green: game.samples.iter().map(|s| i64::from(s.green)).collect(),
-            blue: game.samples.iter().map(|s| i64::from(s.blue)).collect(),
+    /// The fewest cubes of each color the bag must hold for every sample to be possible.
+    fn minimal_bag(&self) -> Cubes {
+        let mut bag = Cubes::default();
+        for (color, &n) in self.samples.iter().flat_map(|s| &s.0) {
+            let count = bag.0.entry(color.clone()).or_default();
+            *count = (*count).max(n);
         }
+        bag
+    }
+
+    fn is_feasible(&self, bag: &Cubes) -> bool {
+        self.samples.iter().all(|s| bag.covers(s))
     }
 }
 
+#[derive(Debug)]
 pub struct Day02 {
     games: Vec<Game>,
+    /// The bag part 1 checks the games against.
+    bag: Cubes,
 }
 
 impl Day02 {
-    fn solve_p1(&self) -> i64 {
-        self.games
+    fn game(&self, id: &str) -> Result<&Game, Box<dyn std::error::Error>> {
+        let id: i64 = id.parse()?;
+        Ok(self
+            .games
             .iter()
-            .filter(|g| g.samples.iter().all(is_sample_valid_part1))
-            .fold(0, |acc, g| acc + g.id)
+            .find(|g| g.id == id)
+            .ok_or(format!("No game {}.", id))?)
     }
 
-    fn solve_p2(&self) -> i64 {
+    fn allowed<'a>(&'a self, bag: &'a Cubes) -> impl Iterator<Item = &'a Game> {
+        self.games.iter().filter(|g| g.is_feasible(bag))
+    }
+
+    /// Every color in the input, since a game which never shows a color needs none of it.
+    fn colors(&self) -> BTreeSet<&str> {
         self.games
             .iter()
-            .map(GameSoA::from)
-            .map(|g| {
-                g.red.into_iter().reduce(i64::max).unwrap()
-                    * g.green.into_iter().reduce(i64::max).unwrap()
-                    * g.blue.into_iter().reduce(i64::max).unwrap()
-            })
-            .sum()
+            .flat_map(|g| &g.samples)
+            .flat_map(|s| s.0.keys())
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The bag given after a query's words, or the configured bag.
+    fn bag_arg(&self, args: &[&str]) -> Result<Cubes, Box<dyn std::error::Error>> {
+        match args {
+            [] => Ok(self.bag.clone()),
+            _ => Cubes::parse_text(&args.join(" ")),
+        }
     }
 }
 
-impl From<String> for Day02 {
-    fn from(input: String) -> Self {
-        Day02 {
-            games: input
-                .lines()
-                .map(|l| {
-                    // Game 1: 3 blue, 4 red;
-                    // prefix: suffix
-                    let (prefix, suffix) = l.split_once(":").unwrap();
-                    Game {
-                        id: prefix
-                            .strip_prefix("Game ")
-                            .unwrap()
-                            .parse::<i64>()
-                            .unwrap(),
-                        samples: suffix.split(";").map(Sample::from).collect(),
-                    }
-                })
-                .collect(),
+/// What the bag designer maximizes over the games a bag allows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Objective {
+    IdSum,
+    Count,
+}
+
+impl TryFrom<&str> for Objective {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "sum" => Ok(Objective::IdSum),
+            "count" => Ok(Objective::Count),
+            _ => Err(format!(
+                "Invalid objective '{}', expected sum or count.",
+                value
+            )),
         }
     }
 }
 
+/// A game's minimal bag as a count for each color, with what allowing the game is worth.
+struct Need {
+    counts: Vec<u64>,
+    score: i128,
+}
+
+/// The best score found for each total number of cubes, with a bag of exactly that many cubes scoring it.
+type Designs = BTreeMap<u128, (i128, Vec<u64>)>;
+
+/// Chooses the count of each color in turn, trying only the counts some game still allowed needs,
+/// since a bag between two such counts allows no more games than the lower one.
+/// The last color is counted up in one pass over the games sorted by their need of it.
+fn search(needs: &[&Need], color: usize, bag: &mut Vec<u64>, cubes: u128, found: &mut Designs) {
+    let mut record = |cubes: u128, score: i128, bag: &[u64]| match found.get(&cubes) {
+        Some(&(best, _)) if best >= score => {}
+        _ => _ = found.insert(cubes, (score, bag.to_vec())),
+    };
+    if bag.is_empty() {
+        return record(cubes, needs.iter().map(|n| n.score).sum(), bag);
+    }
+
+    let mut counts: Vec<u64> = needs.iter().map(|n| n.counts[color]).collect();
+    counts.push(0);
+    counts.sort_unstable();
+    counts.dedup();
+
+    if color + 1 < bag.len() {
+        for count in counts {
+            let allowed: Vec<&Need> = needs
+                .iter()
+                .copied()
+                .filter(|n| n.counts[color] <= count)
+                .collect();
+            bag[color] = count;
+            search(&allowed, color + 1, bag, cubes + u128::from(count), found);
+        }
+        return;
+    }
+
+    let mut sorted = needs.to_vec();
+    sorted.sort_unstable_by_key(|n| n.counts[color]);
+    let (mut score, mut allowed) = (0, sorted.iter().peekable());
+    for count in counts {
+        while let Some(need) = allowed.next_if(|n| n.counts[color] <= count) {
+            score += need.score;
+        }
+        bag[color] = count;
+        record(cubes + u128::from(count), score, bag);
+    }
+}
+
+impl Day02 {
+    /// The best bag of each size which is worth designing, by total number of cubes.
+    fn designs(&self, objective: Objective) -> (Vec<&str>, Designs) {
+        let colors: Vec<&str> = self.colors().into_iter().collect();
+        let needs: Vec<Need> = self
+            .games
+            .iter()
+            .map(|game| {
+                let bag = game.minimal_bag();
+                Need {
+                    counts: colors.iter().map(|color| bag.count(color)).collect(),
+                    score: match objective {
+                        Objective::IdSum => i128::from(game.id),
+                        Objective::Count => 1,
+                    },
+                }
+            })
+            .collect();
+        let mut found = Designs::new();
+        let needs: Vec<&Need> = needs.iter().collect();
+        search(&needs, 0, &mut vec![0; colors.len()], 0, &mut found);
+        (colors, found)
+    }
+
+    /// The bag with the best score holding at most the budget of cubes, using the fewest cubes for that score.
+    fn best_bag(&self, budget: u128, objective: Objective) -> (Cubes, i128) {
+        let (colors, designs) = self.designs(objective);
+        let mut best: Option<&(i128, Vec<u64>)> = None;
+        for design in designs.range(..=budget).map(|(_, design)| design) {
+            if best.is_none_or(|best| design.0 > best.0) {
+                best = Some(design);
+            }
+        }
+        // The empty bag holds no cubes, so there is always a design within the budget.
+        let (score, counts) = best.unwrap();
+        (Cubes::from_counts(&colors, counts), *score)
+    }
+
+    /// The bags where adding cubes first improves the score: no bag scores as well with fewer cubes.
+    fn frontier(&self, objective: Objective) -> Vec<(u128, Cubes, i128)> {
+        let (colors, designs) = self.designs(objective);
+        let mut frontier: Vec<(u128, Cubes, i128)> = vec![];
+        for (&cubes, (score, counts)) in &designs {
+            if frontier.last().is_none_or(|last| *score > last.2) {
+                frontier.push((cubes, Cubes::from_counts(&colors, counts), *score));
+            }
+        }
+        frontier
+    }
+}
+
+/// The objective after a design query's budget, the sum of game ids by default.
+fn objective_arg(args: &[&str]) -> Result<Objective, String> {
+    match args {
+        [] => Ok(Objective::IdSum),
+        [objective] => Objective::try_from(*objective),
+        _ => Err(String::from("Expected one objective, sum or count.")),
+    }
+}
+
+pub const DAY: registry::Day = registry::Day {
+    number: 2,
+    title: "Cube Conundrum",
+    build: |input| Ok(Box::new(Day02::try_from(input)?)),
+};
+
+impl TryFrom<&str> for Day02 {
+    type Error = ParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Ok(Day02 {
+            games: Lines::new(2, input)
+                .map(Game::parse)
+                .collect::<Result<_, _>>()?,
+            bag: Cubes::parse_text("12 red, 13 green, 14 blue").unwrap(),
+        })
+    }
+}
+
 impl puzzle::Solve for Day02 {
-    fn solve(&mut self) -> Result<puzzle::Solution, Box<dyn std::error::Error>> {
-        Ok(puzzle::Solution::Integer(self.solve_p1(), self.solve_p2()))
+    fn solve_p1(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        let sum = self
+            .allowed(&self.bag)
+            .try_fold(0_i64, |sum, g| sum.checked_add(g.id))
+            .ok_or("the sum of game ids overflows")?;
+        Ok(puzzle::Answer::Integer(sum))
+    }
+
+    fn solve_p2(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        let colors = self.colors();
+        let mut sum: i128 = 0;
+        for game in &self.games {
+            let bag = game.minimal_bag();
+            let power = colors
+                .iter()
+                .try_fold(1_i128, |power, color| {
+                    power.checked_mul(i128::from(bag.count(color)))
+                })
+                .ok_or_else(|| format!("the power of game {} overflows", game.id))?;
+            sum = sum
+                .checked_add(power)
+                .ok_or("the sum of powers overflows")?;
+        }
+        Ok(puzzle::Answer::Wide(sum))
+    }
+
+    /// `bag=12 red, 13 green, 14 blue` changes the bag part 1 checks the games against.
+    fn configure(&mut self, setting: &puzzle::Setting) -> Result<(), Box<dyn std::error::Error>> {
+        match setting.key.as_str() {
+            "bag" => self.bag = Cubes::parse_text(&setting.value)?,
+            _ => return Err(format!("Unknown setting '{}'.", setting.key).into()),
+        }
+        Ok(())
+    }
+
+    fn query(&self, args: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+        match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["feasible", id, ref bag @ ..] => {
+                let (game, bag) = (self.game(id)?, self.bag_arg(bag)?);
+                let needs = game.minimal_bag();
+                Ok(match game.is_feasible(&bag) {
+                    true => format!("game {} is feasible with {}", game.id, bag),
+                    false => format!("game {} needs {}, more than {}", game.id, needs, bag),
+                })
+            }
+            ["minimal", id] => {
+                let game = self.game(id)?;
+                Ok(format!(
+                    "game {} needs at least {}",
+                    game.id,
+                    game.minimal_bag()
+                ))
+            }
+            ["allowed", ref bag @ ..] => {
+                let bag = self.bag_arg(bag)?;
+                let ids: Vec<String> = self.allowed(&bag).map(|g| g.id.to_string()).collect();
+                Ok(format!("{} allows games {}", bag, ids.join(", ")))
+            }
+            ["design", budget, ref objective @ ..] => {
+                let objective = objective_arg(objective)?;
+                let (bag, score) = self.best_bag(budget.parse()?, objective);
+                let ids: Vec<String> = self.allowed(&bag).map(|g| g.id.to_string()).collect();
+                Ok(format!(
+                    "{} ({} cubes) allows games {}, scoring {}",
+                    bag,
+                    bag.total(),
+                    ids.join(", "),
+                    score
+                ))
+            }
+            ["frontier", ref objective @ ..] => {
+                let lines: Vec<String> = self
+                    .frontier(objective_arg(objective)?)
+                    .into_iter()
+                    .map(|(cubes, bag, score)| format!("{} cubes: {} scores {}", cubes, bag, score))
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            _ => Err(concat!(
+                "Expected 'feasible <game> [bag]', 'minimal <game>', 'allowed [bag]', ",
+                "'design <cubes> [sum|count]' or 'frontier [sum|count]'."
+            )
+            .into()),
+        }
     }
 }
 
@@ -111,18 +389,118 @@ mod tests {
     use super::*;
     use crate::puzzle::Solve;
 
+    const EXAMPLE: &str = "\
+Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red";
+
+    fn query(day: &Day02, args: &str) -> String {
+        let args: Vec<String> = args.split(' ').map(String::from).collect();
+        day.query(&args).unwrap()
+    }
+
+    #[test]
+    fn invalid_color() {
+        let error = Day02::try_from("Game 1: 3 blue\nGame 2: 4 red, 1 purple!").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 18));
+        assert_eq!(error.text, "purple!");
+        let error = Day02::try_from("Game 1: 3 blue, 4 blue").unwrap_err();
+        assert_eq!(error.message, "'blue' is already counted");
+    }
+
     #[test]
-    fn file_01() {
-        let mut solver: Day02 = std::fs::read_to_string("02/01").unwrap().into();
-        assert_eq!(solver.solve().unwrap(), crate::Solution::Integer(8, 2286));
+    fn any_color_and_count() {
+        let day = Day02::try_from("Game 1: 300 purple, 2 red\nGame 2: 1 purple").unwrap();
+        assert_eq!(day.solve_p1().unwrap(), puzzle::Answer::Integer(0));
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Wide(600));
+
+        let day = Day02::try_from("Game 7: 4294967296 purple").unwrap();
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Wide(4_294_967_296));
     }
 
     #[test]
-    fn file_input() {
-        let mut solver: Day02 = std::fs::read_to_string("02/input").unwrap().into();
+    fn queries() {
+        let day = Day02::try_from(EXAMPLE).unwrap();
+        assert_eq!(
+            query(&day, "minimal 1"),
+            "game 1 needs at least 6 blue, 2 green, 4 red"
+        );
+        assert_eq!(
+            query(&day, "feasible 3"),
+            "game 3 needs 6 blue, 13 green, 20 red, more than 14 blue, 13 green, 12 red"
+        );
+        assert_eq!(
+            query(&day, "feasible 3 20 red, 13 green, 6 blue"),
+            "game 3 is feasible with 6 blue, 13 green, 20 red"
+        );
+        assert_eq!(
+            query(&day, "allowed"),
+            "14 blue, 13 green, 12 red allows games 1, 2"
+        );
+        assert_eq!(
+            query(&day, "allowed 4 blue, 3 green, 1 red"),
+            "4 blue, 3 green, 1 red allows games 2"
+        );
+        assert!(day
+            .query(&[String::from("minimal"), String::from("4")])
+            .is_err());
+    }
+
+    #[test]
+    fn configure_bag() {
+        let mut day = Day02::try_from(EXAMPLE).unwrap();
+        assert_eq!(day.solve_p1().unwrap(), puzzle::Answer::Integer(3));
+        let setting = puzzle::Setting::try_from("bag=20 red, 13 green, 6 blue").unwrap();
+        day.configure(&setting).unwrap();
+        assert_eq!(day.solve_p1().unwrap(), puzzle::Answer::Integer(6));
+        let setting = puzzle::Setting::try_from("bag=lots of red").unwrap();
+        assert!(day.configure(&setting).is_err());
+    }
+
+    #[test]
+    fn design_matches_brute_force() {
+        let day = Day02::try_from(EXAMPLE).unwrap();
+        for objective in [Objective::IdSum, Objective::Count] {
+            for budget in 0..45_u64 {
+                let mut best = 0;
+                for red in 0..=budget {
+                    for green in 0..=budget - red {
+                        let blue = budget - red - green;
+                        let bag =
+                            Cubes::from_counts(&["blue", "green", "red"], &[blue, green, red]);
+                        let score: i128 = day
+                            .allowed(&bag)
+                            .map(|g| match objective {
+                                Objective::IdSum => i128::from(g.id),
+                                Objective::Count => 1,
+                            })
+                            .sum();
+                        best = best.max(score);
+                    }
+                }
+                let (bag, score) = day.best_bag(u128::from(budget), objective);
+                assert_eq!(score, best, "{:?} {}", objective, budget);
+                assert!(bag.total() <= u128::from(budget));
+            }
+        }
+    }
+
+    #[test]
+    fn design_queries() {
+        let day = Day02::try_from(EXAMPLE).unwrap();
+        assert_eq!(
+            query(&day, "design 38"),
+            "6 blue, 3 green, 4 red (13 cubes) allows games 1, 2, scoring 3"
+        );
         assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(2101, 58269)
+            query(&day, "frontier count"),
+            "0 cubes: 0 blue, 0 green, 0 red scores 0\n\
+             8 cubes: 4 blue, 3 green, 1 red scores 1\n\
+             13 cubes: 6 blue, 3 green, 4 red scores 2\n\
+             39 cubes: 6 blue, 13 green, 20 red scores 3"
         );
+        assert!(day
+            .query(&[String::from("frontier"), String::from("most")])
+            .is_err());
     }
 }
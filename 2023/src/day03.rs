@@ -1,123 +1,251 @@
+use crate::grid::{Grid, Position};
+use crate::parse::{Lines, ParseError};
 use crate::puzzle;
-use std::collections::HashMap;
+use crate::registry;
+use std::collections::BTreeMap;
+use std::ops::Range;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-enum State {
-    Searching,
-    ReadingDigits,
-    Validating,
+/// Anything but a digit or a '.' is a symbol.
+fn is_symbol(c: char) -> bool {
+    !c.is_ascii_digit() && c != '.'
 }
 
+/// Writes a position the way parse errors do, counting lines and columns from 1.
+fn describe(pos: Position) -> String {
+    format!("{}:{}", pos.0 + 1, pos.1 + 1)
+}
+
+/// A number in the schematic, with the symbols around it.
+#[derive(Debug, PartialEq)]
+struct Number {
+    value: i64,
+    row: usize,
+    cols: Range<usize>,
+    /// Every symbol touching the number, diagonals included.
+    symbols: Vec<Position>,
+}
+
+impl Number {
+    fn is_part(&self) -> bool {
+        !self.symbols.is_empty()
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} at {}-{}",
+            self.value,
+            describe((self.row, self.cols.start)),
+            self.cols.end
+        )
+    }
+}
+
+/// A gear is a symbol touching exactly some number of parts, '*' and two by default.
+#[derive(Clone, Copy, Debug)]
+struct GearRule {
+    symbol: char,
+    parts: usize,
+}
+
+impl Default for GearRule {
+    fn default() -> Self {
+        GearRule {
+            symbol: '*',
+            parts: 2,
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct Day03 {
-    schematic: Vec<String>,
-    gears: HashMap<(usize, usize), (i64, i64)>,
+    schematic: Grid<char>,
+    numbers: Vec<Number>,
+    /// Every symbol, with the numbers touching it as indices into `numbers`.
+    symbols: BTreeMap<Position, Vec<usize>>,
+    gear_rule: GearRule,
 }
 
 impl Day03 {
-    fn solve_p1(&mut self) -> i64 {
-        (1..(self.schematic.len() - 1))
-            .map(|line_num| self.read_line(line_num).into_iter().sum::<i64>())
-            .sum()
+    /// The symbols of the gear rule's kind, with the numbers touching each.
+    fn gear_symbols(&self) -> impl Iterator<Item = (Position, &[usize])> + '_ {
+        self.symbols
+            .iter()
+            .filter(|(&pos, _)| self.schematic[pos] == self.gear_rule.symbol)
+            .map(|(&pos, numbers)| (pos, numbers.as_slice()))
     }
 
-    fn solve_p2(&mut self) -> i64 {
-        self.gears.drain().map(|(_k, v)| v.0 * v.1).sum()
+    fn gears(&self) -> impl Iterator<Item = (Position, &[usize])> + '_ {
+        self.gear_symbols()
+            .filter(|(_, numbers)| numbers.len() == self.gear_rule.parts)
     }
 
-    fn read_line(&mut self, line_num: usize) -> Vec<i64> {
-        let mut byte_start: usize = 0;
-        let mut byte_end: usize = 0;
+    /// Symbols which would be gears but touch too many numbers.
+    fn overconnected(&self) -> impl Iterator<Item = (Position, &[usize])> + '_ {
+        self.gear_symbols()
+            .filter(|(_, numbers)| numbers.len() > self.gear_rule.parts)
+    }
 
-        let line = self.schematic[line_num].as_bytes();
+    fn unattached(&self) -> impl Iterator<Item = &Number> {
+        self.numbers.iter().filter(|number| !number.is_part())
+    }
 
-        let mut search_state = State::Searching;
-        let mut part_number: i64 = 0;
-        let mut idx: usize = 0;
+    fn describe_symbol(&self, pos: Position, numbers: &[usize]) -> String {
+        let values: Vec<String> = numbers
+            .iter()
+            .map(|&i| self.numbers[i].value.to_string())
+            .collect();
+        format!(
+            "'{}' at {} touches {}: {}",
+            self.schematic[pos],
+            describe(pos),
+            numbers.len(),
+            values.join(", ")
+        )
+    }
+}
 
-        let mut parts: Vec<i64> = vec![];
+/// Finds the runs of digits in a row, with every symbol touching each.
+fn read_row(schematic: &Grid<char>, row: usize) -> Vec<(Range<usize>, Vec<Position>)> {
+    let cells = schematic.row(row);
+    let mut numbers = vec![];
+    let mut col = 0;
 
-        while idx < line.len() {
-            match search_state {
-                State::Searching => match line[idx] {
-                    b'0'..=b'9' => {
-                        byte_start = idx - 1;
-                        part_number = 0;
-                        search_state = State::ReadingDigits;
-                    }
-                    _ => idx += 1,
-                },
-                State::ReadingDigits => match line[idx] {
-                    b'0'..=b'9' => {
-                        part_number = (part_number * 10) + i64::from(line[idx] - b'0');
-                        idx += 1;
-                    }
-                    _ => {
-                        byte_end = idx;
-                        search_state = State::Validating;
-                    }
-                },
-                State::Validating => {
-                    for l in line_num - 1..=line_num + 1 {
-                        let s = &self.schematic[l][byte_start..=byte_end];
-                        for (col, c) in s.chars().enumerate() {
-                            match c {
-                                '*' => {
-                                    parts.push(part_number);
-                                    let pos = (l, byte_start + col);
-                                    let mut gear_parts = self.gears.remove(&pos).unwrap_or((0, 0));
-                                    if gear_parts.0 == 0 {
-                                        gear_parts.0 = part_number;
-                                    } else if gear_parts.1 == 0 {
-                                        gear_parts.1 = part_number;
-                                    } else {
-                                        panic!("A third part attached to a gear!?");
-                                    }
-                                    self.gears.insert(pos, gear_parts);
-                                }
-                                '#' | '%' | '&' | '+' | '-' | '/' | '=' | '@' | '$' => {
-                                    parts.push(part_number);
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                    idx += 1;
-                    part_number = 0;
-                    search_state = State::Searching;
-                }
-            }
+    while col < cells.len() {
+        if !cells[col].is_ascii_digit() {
+            col += 1;
+            continue;
+        }
+        let start = col;
+        while cells.get(col).is_some_and(char::is_ascii_digit) {
+            col += 1;
         }
-        return parts;
+        let symbols = schematic
+            .region(
+                row.saturating_sub(1)..row + 2,
+                start.saturating_sub(1)..col + 1,
+            )
+            .filter(|(_, &c)| is_symbol(c))
+            .map(|(pos, _)| pos)
+            .collect();
+        numbers.push((start..col, symbols));
     }
+    numbers
 }
 
-impl From<String> for Day03 {
-    fn from(input: String) -> Self {
-        // We're going to add a border of '.' so that no digits will be on the edges of the schematic.
-        let mut schematic: Vec<String> = input.lines().map(String::from).collect();
-        let line_length = schematic.first().unwrap().len();
+pub const DAY: registry::Day = registry::Day {
+    number: 3,
+    title: "Gear Ratios",
+    build: |input| Ok(Box::new(Day03::try_from(input)?)),
+};
 
-        schematic.insert(0, ".".repeat(line_length));
-        schematic.push(".".repeat(line_length));
+impl TryFrom<&str> for Day03 {
+    type Error = ParseError;
 
-        for line in schematic.iter_mut() {
-            line.insert(0, '.');
-            line.push('.');
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let schematic = Grid::parse(3, input, |c| c.is_ascii().then_some(c))?;
+
+        let mut symbols: BTreeMap<Position, Vec<usize>> = schematic
+            .positions()
+            .filter(|&pos| is_symbol(schematic[pos]))
+            .map(|pos| (pos, vec![]))
+            .collect();
+        let mut numbers = vec![];
+        for (row, line) in Lines::new(3, input).enumerate() {
+            for (cols, around) in read_row(&schematic, row) {
+                // The schematic is ASCII, so columns are byte offsets into the line.
+                let digits = &line.text()[cols.clone()];
+                let value = line.parse::<i64>(digits)?;
+                for pos in &around {
+                    symbols.entry(*pos).or_default().push(numbers.len());
+                }
+                numbers.push(Number {
+                    value,
+                    row,
+                    cols,
+                    symbols: around,
+                });
+            }
         }
 
-        Day03 {
+        Ok(Day03 {
             schematic,
-            gears: HashMap::new(),
-        }
+            numbers,
+            symbols,
+            gear_rule: GearRule::default(),
+        })
     }
 }
 
 impl puzzle::Solve for Day03 {
-    fn solve(&mut self) -> Result<puzzle::Solution, Box<dyn std::error::Error>> {
-        // We need to call solve_p1() before we can call solve_p2().
-        let part1 = self.solve_p1();
-        let part2 = self.solve_p2();
-        Ok(puzzle::Solution::Integer(part1, part2))
+    fn solve_p1(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        let sum = self
+            .numbers
+            .iter()
+            .filter(|number| number.is_part())
+            .try_fold(0_i64, |sum, number| sum.checked_add(number.value))
+            .ok_or("the sum of part numbers overflows")?;
+        Ok(puzzle::Answer::Integer(sum))
+    }
+
+    fn solve_p2(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        let mut sum: i64 = 0;
+        for (pos, numbers) in self.gears() {
+            let ratio = numbers
+                .iter()
+                .try_fold(1_i64, |ratio, &i| ratio.checked_mul(self.numbers[i].value))
+                .ok_or_else(|| format!("the gear ratio at {} overflows", describe(pos)))?;
+            sum = sum
+                .checked_add(ratio)
+                .ok_or("the sum of gear ratios overflows")?;
+        }
+        Ok(puzzle::Answer::Integer(sum))
+    }
+
+    /// `gear-symbol=c` and `gear-parts=n` change which symbols are gears.
+    fn configure(&mut self, setting: &puzzle::Setting) -> Result<(), Box<dyn std::error::Error>> {
+        let value = setting.value.as_str();
+        match setting.key.as_str() {
+            "gear-symbol" => match value.parse::<char>() {
+                Ok(symbol) if is_symbol(symbol) => self.gear_rule.symbol = symbol,
+                _ => return Err(format!("Invalid gear symbol '{}'.", value).into()),
+            },
+            "gear-parts" => self.gear_rule.parts = value.parse()?,
+            _ => return Err(format!("Unknown setting '{}'.", setting.key).into()),
+        }
+        Ok(())
+    }
+
+    fn query(&self, args: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+        let lines: Vec<String> = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["numbers"] => self
+                .numbers
+                .iter()
+                .map(|number| {
+                    let symbols: Vec<String> = number
+                        .symbols
+                        .iter()
+                        .map(|&pos| format!("'{}' at {}", self.schematic[pos], describe(pos)))
+                        .collect();
+                    match symbols[..] {
+                        [] => format!("{} touches no symbol", number.describe()),
+                        _ => format!("{} touches {}", number.describe(), symbols.join(", ")),
+                    }
+                })
+                .collect(),
+            ["unattached"] => self.unattached().map(Number::describe).collect(),
+            ["gears"] => self
+                .gears()
+                .map(|(pos, numbers)| self.describe_symbol(pos, numbers))
+                .collect(),
+            ["overconnected"] => self
+                .overconnected()
+                .map(|(pos, numbers)| self.describe_symbol(pos, numbers))
+                .collect(),
+            _ => {
+                return Err("Expected 'numbers', 'unattached', 'gears' or 'overconnected'.".into())
+            }
+        };
+        Ok(lines.join("\n"))
     }
 }
 
@@ -126,21 +254,90 @@ mod tests {
     use super::*;
     use crate::puzzle::Solve;
 
+    fn example() -> Day03 {
+        let input = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/03/01")).unwrap();
+        Day03::try_from(input.as_str()).unwrap()
+    }
+
+    fn query(day: &Day03, args: &str) -> String {
+        let args: Vec<String> = args.split(' ').map(String::from).collect();
+        day.query(&args).unwrap()
+    }
+
+    #[test]
+    fn parts_are_independent() {
+        let solver = example();
+        assert_eq!(solver.solve_p2().unwrap(), puzzle::Answer::Integer(467835));
+        assert_eq!(solver.solve_p1().unwrap(), puzzle::Answer::Integer(4361));
+        assert_eq!(solver.solve_p2().unwrap(), puzzle::Answer::Integer(467835));
+    }
+
+    #[test]
+    fn ragged_schematic() {
+        let error = Day03::try_from("467..\n...*.\n..35").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 1));
+    }
+
     #[test]
-    fn file_01() {
-        let mut solver: Day03 = std::fs::read_to_string("03/01").unwrap().into();
+    fn numbers_with_positions() {
+        let day = example();
+        assert_eq!(
+            day.numbers[0],
+            Number {
+                value: 467,
+                row: 0,
+                cols: 0..3,
+                symbols: vec![(1, 3)],
+            }
+        );
+        assert_eq!(query(&day, "unattached"), "114 at 1:6-8\n58 at 6:8-9");
         assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(4361, 467835)
+            query(&day, "gears"),
+            "'*' at 2:4 touches 2: 467, 35\n'*' at 9:6 touches 2: 755, 598"
         );
     }
 
     #[test]
-    fn file_input() {
-        let mut solver: Day03 = std::fs::read_to_string("03/input").unwrap().into();
+    fn any_character_is_a_symbol() {
+        let day = Day03::try_from("1.2.3\n!.?.~\n4.5x6").unwrap();
+        assert_eq!(day.solve_p1().unwrap(), puzzle::Answer::Integer(21));
+        assert_eq!(day.symbols.len(), 4);
+    }
+
+    #[test]
+    fn part_number_zero_is_a_gear_part() {
+        let day = Day03::try_from("0*7").unwrap();
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Integer(0));
+        assert_eq!(query(&day, "gears"), "'*' at 1:2 touches 2: 0, 7");
+    }
+
+    #[test]
+    fn gears_with_n_parts() {
+        let mut day = Day03::try_from("2.3\n.*.\n4.5\n.#9").unwrap();
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Integer(0));
         assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(546563, 91031374)
+            query(&day, "overconnected"),
+            "'*' at 2:2 touches 4: 2, 3, 4, 5"
         );
+
+        day.configure(&puzzle::Setting::try_from("gear-parts=4").unwrap())
+            .unwrap();
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Integer(120));
+        assert_eq!(query(&day, "overconnected"), "");
+
+        day.configure(&puzzle::Setting::try_from("gear-symbol=#").unwrap())
+            .unwrap();
+        day.configure(&puzzle::Setting::try_from("gear-parts=3").unwrap())
+            .unwrap();
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Integer(180));
+        assert!(day
+            .configure(&puzzle::Setting::try_from("gear-symbol=7").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn overflowing_numbers() {
+        let error = Day03::try_from("..99999999999999999999*").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 3));
     }
 }
@@ -1,90 +1,325 @@
+use crate::parse::{Line, Lines, ParseError};
 use crate::puzzle;
-use std::collections::HashSet;
+use crate::registry;
 
-struct Card {
-    winning_numbers: HashSet<i64>,
-    random_numbers: HashSet<i64>,
+/// The numbers on one side of a card, as bits of a fixed-width set since they are all small.
+type Numbers = u128;
+
+/// Reads a card's numbers and counts how many of its numbers are winning numbers.
+fn read_card(line: Line) -> Result<usize, ParseError> {
+    let (_card_num, numbers) = line.split_once(line.text(), ":")?;
+    let (winning, random) = line.split_once(numbers, "|")?;
+    Ok((read_numbers(&line, winning)? & read_numbers(&line, random)?).count_ones() as usize)
 }
 
-impl From<&str> for Card {
-    fn from(line: &str) -> Self {
-        let (_card_num, numbers) = line.split_once(":").unwrap();
-        let (winning, random) = numbers.split_once("|").unwrap();
-        Card {
-            winning_numbers: winning
-                .split_whitespace()
-                .map(|n| n.parse::<i64>().unwrap())
-                .collect(),
-            random_numbers: random
-                .split_whitespace()
-                .map(|n| n.parse::<i64>().unwrap())
-                .collect(),
+fn read_numbers<'a>(line: &Line<'a>, text: &'a str) -> Result<Numbers, ParseError> {
+    let mut numbers: Numbers = 0;
+    for token in text.split_whitespace() {
+        let n: u32 = line.parse(token)?;
+        if n >= Numbers::BITS {
+            let message = format!("numbers must be below {}", Numbers::BITS);
+            return Err(line.error(token, message));
         }
+        numbers |= 1 << n;
     }
+    Ok(numbers)
 }
 
-pub struct Day04 {
-    copies: Vec<i64>,
-    cards: Vec<Card>,
+/// What to do when a card near the end wins copies of cards past the last one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PastEnd {
+    /// Win copies of the cards up to the last one only.
+    Ignore,
+    Error,
 }
 
-impl Day04 {
-    fn solve_p1(&self) -> i64 {
-        self.cards
-            .iter()
-            .map(|c| c.winning_numbers.intersection(&c.random_numbers))
-            .map(|i| i.count())
-            .filter(|n| *n != 0)
-            .map(|n| i64::pow(2, (n - 1).try_into().unwrap()))
-            .sum()
-    }
-
-    fn solve_p2(&mut self) -> i64 {
-        for (i, card) in self.cards.iter().enumerate() {
-            let points = card
-                .winning_numbers
-                .intersection(&card.random_numbers)
-                .count();
-            for x in 1..=points {
-                self.copies[i + x] += self.copies[i];
-            }
+impl TryFrom<&str> for PastEnd {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, String> {
+        match value {
+            "ignore" => Ok(PastEnd::Ignore),
+            "error" => Ok(PastEnd::Error),
+            _ => Err(format!(
+                "Invalid policy '{}', expected ignore or error.",
+                value
+            )),
         }
-        return self.copies.iter().sum();
     }
 }
 
-impl From<String> for Day04 {
-    fn from(input: String) -> Self {
-        Day04 {
-            copies: Vec::from_iter(std::iter::repeat(1).take(input.lines().count())),
-            cards: input.lines().map(Card::from).collect(),
+/// How one card fared in the cascade of copies.
+#[derive(Debug, PartialEq)]
+struct Copies {
+    matches: usize,
+    /// The copies won from earlier cards, not counting the original.
+    received: u64,
+    /// The copies of later cards won by this card's original and copies together.
+    produced: u64,
+}
+
+/// Plays every card in order, each instance of a card winning one copy of each of the next cards it matches.
+fn cascade(matches: &[usize], past_end: PastEnd) -> Result<Vec<Copies>, String> {
+    let mut cascade: Vec<Copies> = matches
+        .iter()
+        .map(|&matches| Copies {
+            matches,
+            received: 0,
+            produced: 0,
+        })
+        .collect();
+    for i in 0..cascade.len() {
+        let overflow = || format!("the copies of card {} overflow", i + 1);
+        let instances = cascade[i].received.checked_add(1).ok_or_else(overflow)?;
+        let won = cascade[i].matches.min(cascade.len() - i - 1);
+        if won < cascade[i].matches && past_end == PastEnd::Error {
+            return Err(format!(
+                "card {} wins copies of {} cards past the last card",
+                i + 1,
+                cascade[i].matches - won
+            ));
         }
+        for later in &mut cascade[i + 1..=i + won] {
+            later.received = later.received.checked_add(instances).ok_or_else(overflow)?;
+        }
+        cascade[i].produced = u64::try_from(won)
+            .ok()
+            .and_then(|won| won.checked_mul(instances))
+            .ok_or_else(overflow)?;
+    }
+    Ok(cascade)
+}
+
+/// Sums the points of cards with these numbers of matches.
+fn points(matches: &[usize]) -> Result<u64, String> {
+    let mut sum: u64 = 0;
+    for (i, &matches) in matches.iter().enumerate() {
+        let points = match matches {
+            0 => 0,
+            n => u32::try_from(n - 1)
+                .ok()
+                .and_then(|n| 2_u64.checked_pow(n))
+                .ok_or_else(|| format!("the points of card {} overflow", i + 1))?,
+        };
+        sum = sum
+            .checked_add(points)
+            .ok_or("the sum of points overflows")?;
+    }
+    Ok(sum)
+}
+
+/// Counts the cards there are once every copy has been won.
+fn total_cards(matches: &[usize], past_end: PastEnd) -> Result<u64, String> {
+    let mut sum: u64 = 0;
+    for copies in cascade(matches, past_end)? {
+        sum = sum
+            .checked_add(copies.received)
+            .and_then(|sum| sum.checked_add(1))
+            .ok_or("the number of cards overflows")?;
+    }
+    Ok(sum)
+}
+
+#[derive(Debug)]
+pub struct Day04 {
+    /// How many of each card's numbers are winning numbers, which is all either part needs.
+    matches: Vec<usize>,
+    past_end: PastEnd,
+}
+
+pub const DAY: registry::Day = registry::Day {
+    number: 4,
+    title: "Scratchcards",
+    build: |input| Ok(Box::new(Day04::try_from(input)?)),
+};
+
+impl TryFrom<&str> for Day04 {
+    type Error = ParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Ok(Day04 {
+            matches: Lines::new(4, input)
+                .map(read_card)
+                .collect::<Result<_, _>>()?,
+            past_end: PastEnd::Ignore,
+        })
     }
 }
 
 impl puzzle::Solve for Day04 {
-    fn solve(&mut self) -> Result<puzzle::Solution, Box<dyn std::error::Error>> {
-        Ok(puzzle::Solution::Integer(self.solve_p1(), self.solve_p2()))
+    fn solve_p1(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        Ok(puzzle::Answer::Unsigned(points(&self.matches)?))
+    }
+
+    fn solve_p2(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        Ok(puzzle::Answer::Unsigned(total_cards(
+            &self.matches,
+            self.past_end,
+        )?))
+    }
+
+    /// `past-end=error` rejects cards winning copies of cards past the last one, rather than ignoring those copies.
+    fn configure(&mut self, setting: &puzzle::Setting) -> Result<(), Box<dyn std::error::Error>> {
+        match setting.key.as_str() {
+            "past-end" => self.past_end = PastEnd::try_from(setting.value.as_str())?,
+            _ => return Err(format!("Unknown setting '{}'.", setting.key).into()),
+        }
+        Ok(())
+    }
+
+    fn query(&self, args: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+        match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["copies"] => {
+                let lines: Vec<String> =
+                    std::iter::zip(1.., cascade(&self.matches, self.past_end)?)
+                        .map(|(card, copies)| {
+                            format!(
+                                "card {}: {} matches, received {} copies, produced {} copies",
+                                card, copies.matches, copies.received, copies.produced
+                            )
+                        })
+                        .collect();
+                Ok(lines.join("\n"))
+            }
+            _ => Err("Expected 'copies'.".into()),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bench;
     use crate::puzzle::Solve;
+    use std::collections::HashSet;
+
+    const EXAMPLE: &str = "\
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+    #[test]
+    fn copies_breakdown() {
+        let day = Day04::try_from(EXAMPLE).unwrap();
+        let cascade = cascade(&day.matches, day.past_end).unwrap();
+        let received: Vec<u64> = cascade.iter().map(|c| c.received).collect();
+        let produced: Vec<u64> = cascade.iter().map(|c| c.produced).collect();
+        assert_eq!(received, [0, 1, 3, 7, 13, 0]);
+        assert_eq!(produced, [4, 4, 8, 8, 0, 0]);
+        assert_eq!(produced.iter().sum::<u64>(), received.iter().sum::<u64>());
+    }
 
     #[test]
-    fn file_01() {
-        let mut solver: Day04 = std::fs::read_to_string("04/01").unwrap().into();
-        assert_eq!(solver.solve().unwrap(), crate::Solution::Integer(13, 30));
+    fn copies_past_the_last_card() {
+        let mut day = Day04::try_from("Card 1: 1 2 | 1 3\nCard 2: 1 2 | 1 2").unwrap();
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Unsigned(3));
+        let setting = puzzle::Setting::try_from("past-end=error").unwrap();
+        day.configure(&setting).unwrap();
+        assert_eq!(
+            day.solve_p2().unwrap_err().to_string(),
+            "card 2 wins copies of 2 cards past the last card"
+        );
     }
 
     #[test]
-    fn file_input() {
-        let mut solver: Day04 = std::fs::read_to_string("04/input").unwrap().into();
+    fn overflowing_copies() {
+        // Each card matches every later card, so card n has 2^(n-1) instances.
+        let numbers: Vec<String> = (1..=70).map(|n| n.to_string()).collect();
+        let card = format!("Card: {0} | {0}\n", numbers.join(" "));
+        let day = Day04::try_from(card.repeat(70).as_str()).unwrap();
         assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(33950, 14814534)
+            day.solve_p2().unwrap_err().to_string(),
+            "the copies of card 62 overflow"
         );
     }
+
+    #[test]
+    fn numbers_too_large() {
+        let error = Day04::try_from("Card 1: 1 2 | 127 128").unwrap_err();
+        assert_eq!((error.column, error.text.as_str()), (19, "128"));
+        assert_eq!(error.message, "numbers must be below 128");
+    }
+
+    /// The cards as sets of numbers, matched again by each part, which the bitsets replaced.
+    struct HashSetDay04 {
+        cards: Vec<(HashSet<i64>, HashSet<i64>)>,
+    }
+
+    impl HashSetDay04 {
+        fn matches(&self) -> Vec<usize> {
+            self.cards
+                .iter()
+                .map(|(winning, random)| winning.intersection(random).count())
+                .collect()
+        }
+    }
+
+    impl puzzle::Solve for HashSetDay04 {
+        fn solve_p1(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+            Ok(puzzle::Answer::Unsigned(points(&self.matches())?))
+        }
+
+        fn solve_p2(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+            let total = total_cards(&self.matches(), PastEnd::Ignore)?;
+            Ok(puzzle::Answer::Unsigned(total))
+        }
+    }
+
+    const HASH_SET_DAY: registry::Day = registry::Day {
+        number: 4,
+        title: "Scratchcards with hash sets",
+        build: |input| {
+            let cards = Lines::new(4, input).map(|line| {
+                let (_card_num, numbers) = line.split_once(line.text(), ":")?;
+                let (winning, random) = line.split_once(numbers, "|")?;
+                let winning = line.parse_all(winning)?.into_iter().collect();
+                Ok((winning, line.parse_all(random)?.into_iter().collect()))
+            });
+            let cards = cards.collect::<Result<_, ParseError>>()?;
+            Ok(Box::new(HashSetDay04 { cards }))
+        },
+    };
+
+    /// Cards shaped like the puzzle input, with few enough matches that copies don't grow without bound.
+    fn generate_cards(count: usize) -> String {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut number = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % 99 + 1
+        };
+        let mut input = String::new();
+        for card in 1..=count {
+            let winning: Vec<String> = (0..5).map(|_| format!("{:2}", number())).collect();
+            let random: Vec<String> = (0..8).map(|_| format!("{:2}", number())).collect();
+            input += &format!(
+                "Card {}: {} | {}\n",
+                card,
+                winning.join(" "),
+                random.join(" ")
+            );
+        }
+        input
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture million_cards`.
+    #[test]
+    #[ignore]
+    fn million_cards() {
+        let input = generate_cards(1_000_000);
+        let bitsets = (DAY.build)(&input).unwrap();
+        let hash_sets = (HASH_SET_DAY.build)(&input).unwrap();
+        assert_eq!(bitsets.solve().unwrap(), hash_sets.solve().unwrap());
+
+        for day in [&DAY, &HASH_SET_DAY] {
+            println!("{}", day.title);
+            bench::bench(day, "generated", &input, 1, 5)
+                .unwrap()
+                .print();
+        }
+    }
 }
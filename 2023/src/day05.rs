@@ -1,273 +1,475 @@
-use crate::puzzle;
+use crate::parse::{Line, Lines, ParseError};
+use crate::puzzle::{self, Part};
+use crate::range_set::RangeSet;
+use crate::registry;
 use std::collections::BTreeMap;
-use std::ops::Bound;
 use std::ops::Range;
 
-trait Offset<T> {
-    fn offset(&self, offset: T) -> Self;
+#[derive(Debug, PartialEq)]
+struct MapEntry {
+    src: Range<i64>,
+    /// How far the entry moves its numbers, which can be further than an i64 holds
+    /// though every number moved lands in one.
+    offset: i128,
 }
 
-impl Offset<i64> for Range<i64> {
-    fn offset(&self, offset: i64) -> Self {
-        (self.start + offset)..(self.end + offset)
-    }
+/// Moves a set of numbers by an offset. The numbers must land within an i64,
+/// which they do when the set lies within the source of a map entry with that offset.
+fn shift(set: &RangeSet<i64>, by: i128) -> RangeSet<i64> {
+    let moved =
+        |n: i64| i64::try_from(i128::from(n) + by).expect("shifted numbers stay within an i64");
+    set.ranges()
+        .iter()
+        .map(|r| moved(r.start)..moved(r.end))
+        .collect()
 }
 
-trait Intersection<T = Self> {
-    fn intersection(&self, other: &T) -> Self;
-}
+impl MapEntry {
+    /// Parses "destination source length", rejecting entries whose ranges don't fit in an i64.
+    fn parse(line: &Line) -> Result<Self, ParseError> {
+        let tokens: Vec<&str> = line.text().split_whitespace().collect();
+        let [dst_token, src_token, length_token] = tokens[..] else {
+            return Err(line.error(
+                line.text(),
+                "expected three numbers: destination start, source start and length",
+            ));
+        };
+        let dst: i64 = line.parse(dst_token)?;
+        let src_start: i64 = line.parse(src_token)?;
+        let length: i64 = line.parse(length_token)?;
 
-impl Intersection<Range<i64>> for Range<i64> {
-    /// Returns the intersection, or overlap, of two ranges.
-    /// If there is not overlap in the ranges, then an empty range where end > start is returned.
-    fn intersection(&self, other: &Range<i64>) -> Self {
-        std::cmp::max(self.start, other.start)..std::cmp::min(self.end, other.end)
+        if length < 0 {
+            return Err(line.error(length_token, "the length can't be negative"));
+        }
+        let src_end = src_start
+            .checked_add(length)
+            .ok_or_else(|| line.error(length_token, "the source range overflows"))?;
+        dst.checked_add(length)
+            .ok_or_else(|| line.error(length_token, "the destination range overflows"))?;
+
+        Ok(MapEntry {
+            src: src_start..src_end,
+            offset: i128::from(dst) - i128::from(src_start),
+        })
     }
 }
 
-fn range_remainder(this: &Range<i64>, other: &Range<i64>) -> Vec<Range<i64>> {
-    // There are 6 cases to consider
+#[derive(Debug, Default)]
+struct Alminac {
+    map: BTreeMap<i64, MapEntry>,
+}
 
-    // 1. No overlap, other is less than this
-    if other.end <= this.start {
-        return vec![this.clone()];
-    }
+impl Alminac {
+    /// Transforms a set of numbers, moving the parts covered by a map entry by its offset
+    /// and leaving the rest where they are.
+    fn lookup_range(&self, src: &RangeSet<i64>) -> RangeSet<i64> {
+        let Some(end) = src.ranges().last().map(|r| r.end) else {
+            return RangeSet::new();
+        };
 
-    // 2. Overlap on the lower part of this
-    if other.start <= this.start && other.end < this.end {
-        return vec![{ other.end..this.end }];
-    }
+        let mut unmapped = src.clone();
+        let mut mapped = RangeSet::new();
 
-    // 3. Overlap in the middle of this, but on neither ends
-    if other.start > this.start && other.end < this.end {
-        return vec![{ this.start..other.start }, { other.end..this.end }];
+        // Only entries starting before the end of the input can apply to it.
+        for entry in self.map.range(..end).map(|kvp| kvp.1) {
+            let covered = unmapped.intersection(&RangeSet::from(entry.src.clone()));
+            mapped = mapped.union(&shift(&covered, entry.offset));
+            unmapped = unmapped.difference(&covered);
+        }
+        mapped.union(&unmapped)
     }
+}
 
-    // 4. Overlap in the upper part of this
-    if other.start > this.start && other.end >= this.end {
-        return vec![{ this.start..other.start }];
+impl Alminac {
+    /// The whole map as pieces in order, covering every i64 but the largest,
+    /// with the numbers no entry covers as pieces of offset 0.
+    fn pieces(&self) -> Vec<MapEntry> {
+        let mut pieces = vec![];
+        let mut start = i64::MIN;
+        for entry in self.map.values() {
+            let src = entry.src.clone();
+            if src.is_empty() {
+                continue;
+            }
+            if start < src.start {
+                pieces.push(MapEntry {
+                    src: start..src.start,
+                    offset: 0,
+                });
+            }
+            start = src.end;
+            pieces.push(MapEntry {
+                src,
+                offset: entry.offset,
+            });
+        }
+        if start < i64::MAX {
+            pieces.push(MapEntry {
+                src: start..i64::MAX,
+                offset: 0,
+            });
+        }
+        pieces
     }
 
-    // 5. No overlap, other is greater than this
-    if other.start >= this.end {
-        return vec![this.clone()];
+    /// Composes two maps into the one map which applies this one and then `next`.
+    fn then(&self, next: &Alminac) -> Alminac {
+        let next_pieces = next.pieces();
+        let mut map = BTreeMap::new();
+        for piece in self.pieces() {
+            let image = shift(&RangeSet::from(piece.src.clone()), piece.offset);
+            for next_piece in &next_pieces {
+                let overlap = image.intersection(&RangeSet::from(next_piece.src.clone()));
+                if overlap.ranges().is_empty() {
+                    continue;
+                }
+                let offset = piece.offset + next_piece.offset;
+                // Numbers which end up where they started need no entry.
+                if offset == 0 {
+                    continue;
+                }
+                for range in shift(&overlap, -piece.offset).ranges() {
+                    let entry = MapEntry {
+                        src: range.clone(),
+                        offset,
+                    };
+                    map.insert(range.start, entry);
+                }
+            }
+        }
+        Alminac { map }
     }
 
-    // 6. other completely overlaps this
-    if other.start <= this.start && other.end >= this.end {
-        return vec![];
+    /// Every number which the map sends into `dst`.
+    fn preimage(&self, dst: &RangeSet<i64>) -> RangeSet<i64> {
+        self.pieces()
+            .iter()
+            .map(|piece| {
+                let image = shift(&RangeSet::from(piece.src.clone()), piece.offset);
+                shift(&dst.intersection(&image), -piece.offset)
+            })
+            .fold(RangeSet::new(), |preimage, part| preimage.union(&part))
     }
 
-    panic!("There's a bug in range_remainder!");
-}
-
-#[derive(Debug, PartialEq)]
-struct MapEntry {
-    src: Range<i64>,
-    offset: i64,
-}
+    /// The numbers between the lowest and highest mapped ones which no entry covers, and so map to themselves.
+    /// Numbers outside that span always map to themselves too.
+    fn gaps(&self) -> RangeSet<i64> {
+        let covered: RangeSet<i64> = self.map.values().map(|e| e.src.clone()).collect();
+        match (covered.ranges().first(), covered.ranges().last()) {
+            (Some(first), Some(last)) => RangeSet::from(first.start..last.end).difference(&covered),
+            _ => RangeSet::new(),
+        }
+    }
 
-impl From<&str> for MapEntry {
-    fn from(line: &str) -> Self {
-        let mut iter = line.trim().split_whitespace();
-        let dst: i64 = iter.next().unwrap().parse().unwrap();
-        let src_start = iter.next().unwrap().parse().unwrap();
-        let range: i64 = iter.next().unwrap().parse().unwrap();
-        let src = src_start..src_start + range;
-        let offset = dst - src_start;
-        MapEntry { src, offset }
+    /// Parses the entries of a map, which must not overlap so every number has at most one way through.
+    fn parse<'a>(lines: impl Iterator<Item = Line<'a>>) -> Result<Self, ParseError> {
+        let mut map: BTreeMap<i64, MapEntry> = BTreeMap::new();
+        for line in lines {
+            let entry = MapEntry::parse(&line)?;
+            // Point errors at the source start.
+            let src_token = line.text().split_whitespace().nth(1).unwrap();
+            if map.contains_key(&entry.src.start) {
+                return Err(line.error(
+                    src_token,
+                    format!("source start {} is already mapped", entry.src.start),
+                ));
+            }
+            // Only the closest entries on either side can overlap a new one.
+            let before = map.range(..entry.src.start).next_back();
+            let after = map.range(entry.src.start..).next();
+            for (_, other) in before.into_iter().chain(after) {
+                if other.src.start < entry.src.end && entry.src.start < other.src.end {
+                    return Err(line.error(
+                        src_token,
+                        format!(
+                            "source range {}..{} overlaps {}..{}",
+                            entry.src.start, entry.src.end, other.src.start, other.src.end
+                        ),
+                    ));
+                }
+            }
+            map.insert(entry.src.start, entry);
+        }
+        Ok(Alminac { map })
     }
 }
 
 #[derive(Debug)]
-struct Alminac {
-    map: BTreeMap<i64, MapEntry>,
+pub struct Day05 {
+    /// Each seed of part 1 as a range holding just that seed.
+    seeds: Vec<Range<i64>>,
+    seed_ranges: Vec<Range<i64>>,
+    /// Every map, keyed by the categories it maps from and to.
+    maps: BTreeMap<(String, String), Alminac>,
+    /// The maps leading from seed to location composed into one, if there are any.
+    seed_to_location: Option<Alminac>,
 }
 
-impl Alminac {
-    /// Transforms a range of numbers into a collection of transformed ranges.
-    fn lookup_range(&self, src: Range<i64>) -> Vec<Range<i64>> {
-        let mut input_ranges = vec![src.clone()];
-        let mut output_ranges = vec![];
-
-        // Get all B-tree entries with src ranges starting up to and including the end of our input range.
-        let map_entries = self.map.range((Bound::Unbounded, Bound::Excluded(src.end)));
-
-        // For each potentially applicable range, find the intersection, and offset if needed.
-        while !input_ranges.is_empty() {
-            let input = input_ranges.pop().unwrap();
-
-            // This map entry does not transform the input.
-            // We'll use it as the default if there are no "real" transformations applicable.
-            let identity_map_entry = MapEntry {
-                src: input.clone(),
-                offset: 0,
-            };
-
-            // Find a transformation which applies to the input, if none, use the identity element.
-            let transform = map_entries
-                .clone()
-                .map(|kvp| kvp.1)
-                .find(|e| !input.intersection(&e.src).is_empty())
-                .unwrap_or(&identity_map_entry);
+/// Reads one "X-to-Y map:" section, which ends at an empty line or the end of the input,
+/// returning the categories X and Y with the map.
+fn read_map(header: Line, lines: &mut Lines) -> Result<(String, String, Alminac), ParseError> {
+    let Some(name) = header.text().strip_suffix(" map:") else {
+        return Err(header.error(
+            header.text(),
+            "expected a map header like 'seed-to-soil map:'",
+        ));
+    };
+    let (source, destination) = header.split_once(name, "-to-")?;
+    for category in [source, destination] {
+        if category.is_empty() || category.contains(char::is_whitespace) {
+            return Err(header.error(category, format!("invalid category '{}'", category)));
+        }
+    }
+    let alminac = Alminac::parse(lines.by_ref().take_while(|l| !l.text().is_empty()))?;
+    Ok((String::from(source), String::from(destination), alminac))
+}
 
-            // Transform the input and push it to the output_ranges vec
-            output_ranges.push(input.intersection(&transform.src).offset(transform.offset));
+type SeedRange = Range<i64>;
+
+/// Reads "seeds: 79 14 55 13" as single seeds for part 1 and as pairs of start and length for part 2,
+/// rejecting seeds and ranges which don't fit in an i64.
+fn read_seeds(line: Line) -> Result<(Vec<SeedRange>, Vec<SeedRange>), ParseError> {
+    let tokens: Vec<&str> = line
+        .strip_prefix(line.text(), "seeds: ")?
+        .split_whitespace()
+        .collect();
+    let mut seeds = vec![];
+    for token in &tokens {
+        let seed: i64 = line.parse(token)?;
+        let end = seed
+            .checked_add(1)
+            .ok_or_else(|| line.error(token, "the seed is too large"))?;
+        seeds.push(seed..end);
+    }
 
-            // If there are any leftover ranges which must be considered, put them into the input_ranges vec
-            input_ranges.extend(
-                range_remainder(&input, &transform.src)
-                    .into_iter()
-                    .filter(|r| !r.is_empty()),
-            );
+    let mut seed_ranges = vec![];
+    for pair in tokens.chunks(2) {
+        let [start_token, length_token] = pair[..] else {
+            let message = "expected a length for the last seed range";
+            return Err(line.missing_after(pair[0], message));
+        };
+        let start: i64 = line.parse(start_token)?;
+        let length: i64 = line.parse(length_token)?;
+        if length < 0 {
+            return Err(line.error(length_token, "the length can't be negative"));
         }
-        return output_ranges;
+        let end = start
+            .checked_add(length)
+            .ok_or_else(|| line.error(length_token, "the seed range overflows"))?;
+        seed_ranges.push(start..end);
     }
+    Ok((seeds, seed_ranges))
 }
 
-impl From<&str> for Alminac {
-    fn from(lines: &str) -> Self {
-        Alminac {
-            map: BTreeMap::from_iter(lines.lines().map(MapEntry::from).map(|e| (e.src.start, e))),
+/// Reads a query argument like "5..10", or "7" for a single number.
+fn parse_range(text: &str) -> Result<Range<i64>, Box<dyn std::error::Error>> {
+    match text.split_once("..") {
+        Some((start, end)) => Ok(start.parse()?..end.parse()?),
+        None => {
+            let n: i64 = text.parse()?;
+            Ok(n..n
+                .checked_add(1)
+                .ok_or(format!("{} is too large for a range", n))?)
         }
     }
 }
 
-impl From<Vec<&str>> for Alminac {
-    fn from(lines: Vec<&str>) -> Self {
-        Alminac {
-            map: BTreeMap::from_iter(
-                lines
-                    .into_iter()
-                    .map(MapEntry::from)
-                    .map(|e| (e.src.start, e)),
-            ),
+impl Day05 {
+    fn seeds(&self, part: Part) -> RangeSet<i64> {
+        match part {
+            // We can use ranges to solve part 1!
+            Part::One => self.seeds.iter().cloned().collect(),
+            Part::Two => self.seed_ranges.iter().cloned().collect(),
         }
     }
-}
 
-pub struct Day05 {
-    seeds: Vec<i64>,
-    seed_ranges: Vec<Range<i64>>,
-    seed_to_soil: Alminac,
-    soil_to_fertilizer: Alminac,
-    fertilizer_to_water: Alminac,
-    water_to_light: Alminac,
-    light_to_temperature: Alminac,
-    temperature_to_humidity: Alminac,
-    humidity_to_location: Alminac,
-}
+    /// The shortest chain of maps from one category to another, as the categories it passes through.
+    fn path<'a>(&'a self, from: &'a str, to: &str) -> Option<Vec<&'a str>> {
+        // A breadth first search, remembering where each category was first reached from.
+        let mut reached_from: BTreeMap<&str, &str> = BTreeMap::new();
+        let mut queue = std::collections::VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut path = vec![category];
+                while let Some(&previous) = path.last().and_then(|c| reached_from.get(c)) {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (source, destination) in self.maps.keys() {
+                if source == category
+                    && destination != from
+                    && !reached_from.contains_key(destination.as_str())
+                {
+                    reached_from.insert(destination, category);
+                    queue.push_back(destination);
+                }
+            }
+        }
+        None
+    }
 
-impl Day05 {
-    fn solve_p1(&mut self) -> i64 {
-        self.seeds
-            .iter()
-            .cloned()
-            .map(|s| (s..s + 1)) // We can use ranges to solve part 1!
-            .flat_map(|r| self.seed_to_soil.lookup_range(r))
-            .flat_map(|r| self.soil_to_fertilizer.lookup_range(r))
-            .flat_map(|r| self.fertilizer_to_water.lookup_range(r))
-            .flat_map(|r| self.water_to_light.lookup_range(r))
-            .flat_map(|r| self.light_to_temperature.lookup_range(r))
-            .flat_map(|r| self.temperature_to_humidity.lookup_range(r))
-            .flat_map(|r| self.humidity_to_location.lookup_range(r))
-            .map(Range::min)
+    /// Composes the maps along a path into one.
+    fn compose(&self, path: &[&str]) -> Alminac {
+        path.windows(2)
+            .map(|step| &self.maps[&(String::from(step[0]), String::from(step[1]))])
+            .fold(Alminac::default(), |composed, map| composed.then(map))
+    }
+
+    fn seed_to_location(&self) -> Result<&Alminac, Box<dyn std::error::Error>> {
+        Ok(self
+            .seed_to_location
+            .as_ref()
+            .ok_or("no chain of maps leads from seed to location")?)
+    }
+
+    /// The lowest location reached from the seeds of a part, and the lowest seed which reaches it.
+    fn lowest_location(&self, part: Part) -> Result<(i64, i64), Box<dyn std::error::Error>> {
+        let seeds = self.seeds(part);
+        let location = self
+            .seed_to_location()?
+            .lookup_range(&seeds)
             .min()
-            .unwrap()
-            .unwrap()
-    }
-
-    fn solve_p2(&mut self) -> i64 {
-        self.seed_ranges
-            .clone()
-            .into_iter()
-            .flat_map(|r| self.seed_to_soil.lookup_range(r))
-            .flat_map(|r| self.soil_to_fertilizer.lookup_range(r))
-            .flat_map(|r| self.fertilizer_to_water.lookup_range(r))
-            .flat_map(|r| self.water_to_light.lookup_range(r))
-            .flat_map(|r| self.light_to_temperature.lookup_range(r))
-            .flat_map(|r| self.temperature_to_humidity.lookup_range(r))
-            .flat_map(|r| self.humidity_to_location.lookup_range(r))
-            .map(Range::min)
+            .ok_or("there are no seeds")?;
+        let seed = self
+            .seed_to_location()?
+            .preimage(&RangeSet::from(location..location + 1))
+            .intersection(&seeds)
             .min()
-            .unwrap()
-            .unwrap()
+            .ok_or("no seed reaches the lowest location")?;
+        Ok((seed, location))
     }
 }
 
-impl From<String> for Day05 {
-    fn from(input: String) -> Self {
-        let seed_ranges = input
-            .lines()
-            .next()
-            .unwrap()
-            .strip_prefix("seeds: ")
-            .unwrap()
-            .split_whitespace()
-            .map(|n| n.parse::<i64>().unwrap())
-            .collect::<Vec<i64>>()
-            .chunks_exact(2)
-            .map(|s| (s[0]..s[0] + s[1]))
-            .collect();
-
-        let mut lines = input.lines();
-        let seeds = Vec::from_iter(
-            lines
-                .next()
-                .unwrap()
-                .strip_prefix("seeds: ")
-                .unwrap()
-                .split_whitespace()
-                .map(|n| n.parse::<i64>().unwrap()),
-        );
-
-        assert_eq!(lines.next(), Some(""));
-
-        assert_eq!(lines.next(), Some("seed-to-soil map:"));
-        let seed_to_soil =
-            Alminac::from(lines.by_ref().take_while(|l| *l != "").collect::<Vec<_>>());
+pub const DAY: registry::Day = registry::Day {
+    number: 5,
+    title: "If You Give A Seed A Fertilizer",
+    build: |input| Ok(Box::new(Day05::try_from(input)?)),
+};
 
-        assert_eq!(lines.next(), Some("soil-to-fertilizer map:"));
-        let soil_to_fertilizer =
-            Alminac::from(lines.by_ref().take_while(|l| *l != "").collect::<Vec<_>>());
+impl TryFrom<&str> for Day05 {
+    type Error = ParseError;
 
-        assert_eq!(lines.next(), Some("fertilizer-to-water map:"));
-        let fertilizer_to_water =
-            Alminac::from(lines.by_ref().take_while(|l| *l != "").collect::<Vec<_>>());
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let mut lines = Lines::new(5, input);
 
-        assert_eq!(lines.next(), Some("water-to-light map:"));
-        let water_to_light =
-            Alminac::from(lines.by_ref().take_while(|l| *l != "").collect::<Vec<_>>());
+        let seeds_line = lines.expect("the list of seeds")?;
+        let (seeds, seed_ranges) = read_seeds(seeds_line)?;
 
-        assert_eq!(lines.next(), Some("light-to-temperature map:"));
-        let light_to_temperature =
-            Alminac::from(lines.by_ref().take_while(|l| *l != "").collect::<Vec<_>>());
+        lines.expect_exact("")?;
 
-        assert_eq!(lines.next(), Some("temperature-to-humidity map:"));
-        let temperature_to_humidity =
-            Alminac::from(lines.by_ref().take_while(|l| *l != "").collect::<Vec<_>>());
-
-        assert_eq!(lines.next(), Some("humidity-to-location map:"));
-        let humidity_to_location =
-            Alminac::from(lines.by_ref().take_while(|l| *l != "").collect::<Vec<_>>());
+        // The maps can come in any order, with blank lines between them.
+        let mut maps = BTreeMap::new();
+        while let Some(header) = lines.next() {
+            if header.text().is_empty() {
+                continue;
+            }
+            let (source, destination, alminac) = read_map(header, &mut lines)?;
+            if maps.contains_key(&(source.clone(), destination.clone())) {
+                return Err(header.error(
+                    header.text(),
+                    format!("the {}-to-{} map is already given", source, destination),
+                ));
+            }
+            maps.insert((source, destination), alminac);
+        }
 
-        Day05 {
+        let mut day = Day05 {
             seeds,
             seed_ranges,
-            seed_to_soil,
-            soil_to_fertilizer,
-            fertilizer_to_water,
-            water_to_light,
-            light_to_temperature,
-            temperature_to_humidity,
-            humidity_to_location,
-        }
+            maps,
+            seed_to_location: None,
+        };
+        day.seed_to_location = day.path("seed", "location").map(|path| day.compose(&path));
+        Ok(day)
     }
 }
 
 impl puzzle::Solve for Day05 {
-    fn solve(&mut self) -> Result<puzzle::Solution, Box<dyn std::error::Error>> {
-        Ok(puzzle::Solution::Integer(self.solve_p1(), self.solve_p2()))
+    fn solve_p1(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        let (_seed, location) = self.lowest_location(Part::One)?;
+        Ok(puzzle::Answer::Integer(location))
+    }
+
+    fn solve_p2(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        let (_seed, location) = self.lowest_location(Part::Two)?;
+        Ok(puzzle::Answer::Integer(location))
+    }
+
+    /// `lowest [1|2]` explains which seed reaches the lowest location,
+    /// `location <seed>` looks up one seed, `seeds <start..end>` finds the seeds reaching some locations,
+    /// `map <from> <to> [start..end]` follows the maps between any two categories,
+    /// and `gaps` lists the numbers each map leaves unchanged between its entries.
+    fn query(&self, args: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+        match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["lowest", ref part @ ..] => {
+                let parts = match part {
+                    [part] => vec![Part::try_from(*part)?],
+                    _ => vec![Part::One, Part::Two],
+                };
+                let lines = parts
+                    .into_iter()
+                    .map(|part| {
+                        let (seed, location) = self.lowest_location(part)?;
+                        Ok(format!(
+                            "{}: seed {} reaches the lowest location, {}",
+                            part, seed, location
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+                Ok(lines.join("\n"))
+            }
+            ["location", seed] => {
+                let seed: i64 = seed.parse()?;
+                let end = seed
+                    .checked_add(1)
+                    .ok_or(format!("{} is too large for a range", seed))?;
+                let location = self
+                    .seed_to_location()?
+                    .lookup_range(&RangeSet::from(seed..end));
+                Ok(format!(
+                    "seed {} reaches location {}",
+                    seed,
+                    location.min().unwrap()
+                ))
+            }
+            ["seeds", locations] => {
+                let locations = RangeSet::from(parse_range(locations)?);
+                Ok(format!(
+                    "locations {} are reached from seeds {}",
+                    locations,
+                    self.seed_to_location()?.preimage(&locations)
+                ))
+            }
+            ["gaps"] => {
+                let lines: Vec<String> = self
+                    .maps
+                    .iter()
+                    .map(|((source, destination), map)| {
+                        format!("{}-to-{}: {}", source, destination, map.gaps())
+                    })
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            ["map", from, to, ref numbers @ ..] => {
+                let path = self
+                    .path(from, to)
+                    .ok_or(format!("No chain of maps leads from {} to {}.", from, to))?;
+                let mut lines = vec![path.join(" -> ")];
+                if let [numbers] = numbers {
+                    let numbers = RangeSet::from(parse_range(numbers)?);
+                    let image = self.compose(&path).lookup_range(&numbers);
+                    lines.push(format!("{} {} map to {} {}", from, numbers, to, image));
+                }
+                Ok(lines.join("\n"))
+            }
+            _ => Err(concat!(
+                "Expected 'lowest [1|2]', 'location <seed>', 'seeds <start..end>' ",
+                "'map <from> <to> [start..end]' or 'gaps'."
+            )
+            .into()),
+        }
     }
 }
 
@@ -275,11 +477,10 @@ impl puzzle::Solve for Day05 {
 mod tests {
     use super::*;
     use crate::puzzle::Solve;
-    use std::collections::HashSet;
 
     #[test]
     fn construct_map_entry() {
-        let entry = MapEntry::from("100 200 25");
+        let entry = MapEntry::parse(&Lines::new(5, "100 200 25").next().unwrap()).unwrap();
         assert_eq!(
             entry,
             MapEntry {
@@ -289,84 +490,214 @@ mod tests {
         );
     }
 
+    fn alminac_lookup_range_helper(alminac_str: &str, range: Range<i64>, expected: &[Range<i64>]) {
+        let alminac = Alminac::parse(Lines::new(5, alminac_str)).unwrap();
+        assert_eq!(
+            alminac.lookup_range(&RangeSet::from(range)),
+            expected.iter().cloned().collect()
+        );
+    }
+
+    #[test]
+    fn alminac_lookup_range_01() {
+        alminac_lookup_range_helper("100 10 10", 10..15, &[{ 100..105 }]);
+    }
+
     #[test]
-    fn range_offset() {
-        assert_eq!((0..5).offset(10), (10..15));
-        assert_eq!((10..15).offset(-10), (0..5));
+    fn alminac_lookup_range_02() {
+        alminac_lookup_range_helper("100 10 10", 10..25, &[100..110, 20..25]);
+    }
+
+    #[test]
+    fn alminac_lookup_range_03() {
+        alminac_lookup_range_helper("100 10 10", 1..11, &[100..101, 1..10]);
+    }
+
+    fn example() -> Day05 {
+        let input = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/05/01")).unwrap();
+        Day05::try_from(input.as_str()).unwrap()
     }
 
     #[test]
-    fn range_intersection() {
-        assert_eq!((10..20).intersection(&(20..25)), (20..20));
-        assert_eq!((0..5).intersection(&(3..10)), (3..5));
-        assert_eq!((10..15).intersection(&(20..30)), (20..15));
-        assert!((10..15).intersection(&(20..30)).is_empty());
+    fn composed_map_matches_each_map_in_turn() {
+        let day = example();
+        let path = day.path("seed", "location").unwrap();
+        assert_eq!(path.len(), 8);
+        for seeds in [0..100, 79..93, 55..68, -5..3] {
+            let seeds = RangeSet::from(seeds);
+            let chained = path.windows(2).fold(seeds.clone(), |set, step| {
+                day.maps[&(String::from(step[0]), String::from(step[1]))].lookup_range(&set)
+            });
+            assert_eq!(
+                day.seed_to_location().unwrap().lookup_range(&seeds),
+                chained
+            );
+        }
     }
 
     #[test]
-    fn test_range_remainder() {
-        assert_eq!(range_remainder(&(10..25), &(10..20)), vec![{ 20..25 }]);
-        assert_eq!(range_remainder(&(5..10), &(0..5)), vec![{ 5..10 }]);
-        assert_eq!(range_remainder(&(5..10), &(0..6)), vec![{ 6..10 }]);
+    fn preimage() {
+        // Soil 50 is only reached from seed 98, as seed 50 is moved to soil 52.
+        let alminac = Alminac::parse(Lines::new(5, "50 98 2\n52 50 48")).unwrap();
         assert_eq!(
-            range_remainder(&(5..10), &(6..8)),
-            vec![{ 5..6 }, { 8..10 }]
+            alminac.preimage(&RangeSet::from(50..51)),
+            [{ 98..99 }].into_iter().collect()
         );
-        assert_eq!(range_remainder(&(5..10), &(7..20)), vec![{ 5..7 }]);
-        assert_eq!(range_remainder(&(5..10), &(10..20)), vec![{ 5..10 }]);
-        assert_eq!(range_remainder(&(1..10), &(0..5)), vec![{ 5..10 }]);
-        assert_eq!(range_remainder(&(10..15), &(0..5)), vec![{ 10..15 }]);
-        assert_eq!(range_remainder(&(10..15), &(5..20)), vec![]);
-        assert_eq!(range_remainder(&(1..11), &(10..20)), vec![{ 1..10 }]);
-    }
-
-    fn alminac_lookup_range_helper(
-        alminac_str: &str,
-        range: Range<i64>,
-        expected: HashSet<Range<i64>>,
-    ) {
-        let alminac = Alminac::from(alminac_str);
-        let actual: HashSet<Range<i64>> = HashSet::from_iter(alminac.lookup_range(range));
-
-        // If there is no difference in the sets, then everything is okay.
-        if actual.symmetric_difference(&expected).count() == 0 {
-            return;
-        };
+        assert_eq!(
+            alminac.preimage(&RangeSet::from(99..101)),
+            [97..98, 100..101].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn explain_lowest_location() {
+        let day = example();
+        assert_eq!(day.lowest_location(Part::One).unwrap(), (13, 35));
+        assert_eq!(day.lowest_location(Part::Two).unwrap(), (82, 46));
+        assert_eq!(
+            day.query(&[String::from("location"), String::from("79")])
+                .unwrap(),
+            "seed 79 reaches location 82"
+        );
+    }
 
-        // Otherwise, we have an issue
-        dbg!(&actual);
-        dbg!(&expected);
-        dbg!(actual.symmetric_difference(&expected).collect::<Vec<_>>());
-        assert_eq!(actual.symmetric_difference(&expected).count(), 0);
+    #[test]
+    fn reordered_and_extra_maps() {
+        let input = concat!(
+            "seeds: 1 2\n\n",
+            "soil-to-location map:\n10 0 5\n\n\n",
+            "seed-to-moon map:\n0 0 1\n\n",
+            "seed-to-soil map:\n3 1 2\n",
+        );
+        let day = Day05::try_from(input).unwrap();
+        assert_eq!(
+            day.path("seed", "location"),
+            Some(vec!["seed", "soil", "location"])
+        );
+        assert_eq!(day.path("moon", "soil"), None);
+        assert_eq!(day.solve_p1().unwrap(), puzzle::Answer::Integer(13));
+        assert_eq!(
+            day.query(&["map", "soil", "location", "3..6"].map(String::from))
+                .unwrap(),
+            "soil -> location\nsoil {3..6} map to location {5..6, 13..15}"
+        );
     }
 
     #[test]
-    fn alminac_lookup_range_01() {
-        alminac_lookup_range_helper("100 10 10", 10..15, HashSet::from([100..105]));
+    fn no_path_to_location() {
+        let day = Day05::try_from("seeds: 1 2\n\nseed-to-soil map:\n3 1 2\n").unwrap();
+        assert!(day.solve_p1().is_err());
+    }
+
+    fn map_error(text: &str) -> ParseError {
+        Alminac::parse(Lines::new(5, text)).unwrap_err()
     }
 
     #[test]
-    fn alminac_lookup_range_02() {
-        alminac_lookup_range_helper("100 10 10", 10..25, HashSet::from([100..110, 20..25]));
+    fn invalid_map_entries() {
+        let error = map_error("0 9223372036854775800 10");
+        assert_eq!(
+            (error.column, error.message.as_str()),
+            (23, "the source range overflows")
+        );
+        let error = map_error("9223372036854775800 0 10");
+        assert_eq!(error.message, "the destination range overflows");
+        let error = map_error("0 5 -1");
+        assert_eq!(error.message, "the length can't be negative");
     }
 
     #[test]
-    fn alminac_lookup_range_03() {
-        alminac_lookup_range_helper("100 10 10", 1..11, HashSet::from([100..101, 1..10]));
+    fn offsets_beyond_an_i64() {
+        let alminac =
+            Alminac::parse(Lines::new(5, "9223372036854775800 -9223372036854775800 1")).unwrap();
+        assert_eq!(
+            alminac.lookup_range(&RangeSet::from(-9223372036854775800..-9223372036854775799)),
+            RangeSet::from(9223372036854775800..9223372036854775801)
+        );
+
+        // Each step fits in an i64, though seed to location moves by -1.8e19.
+        let day = Day05::try_from(concat!(
+            "seeds: 9000000000000000000 1\n\n",
+            "seed-to-soil map:\n0 9000000000000000000 1\n\n",
+            "soil-to-location map:\n-9000000000000000000 0 1\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            day.solve_p1().unwrap(),
+            puzzle::Answer::Integer(-9000000000000000000)
+        );
+
+        let day =
+            Day05::try_from("seeds: 0 1\n\nseed-to-location map:\n-9223372036854775808 0 1\n")
+                .unwrap();
+        assert_eq!(day.solve_p1().unwrap(), puzzle::Answer::Integer(i64::MIN));
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Integer(i64::MIN));
+        let day = Day05::try_from(concat!(
+            "seeds: 0 1\n\n",
+            "seed-to-soil map:\n-9223372036854775808 0 1\n\n",
+            "soil-to-location map:\n5 -9223372036854775808 1\n",
+        ))
+        .unwrap();
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Integer(5));
+    }
+
+    #[test]
+    fn invalid_seeds() {
+        let error = Day05::try_from("seeds: 9223372036854775000 1000\n").unwrap_err();
+        assert_eq!(
+            (error.column, error.message.as_str()),
+            (28, "the seed range overflows")
+        );
+        let error = Day05::try_from("seeds: 79 14 55\n").unwrap_err();
+        assert_eq!(
+            (error.column, error.message.as_str()),
+            (16, "expected a length for the last seed range")
+        );
+        let error = Day05::try_from("seeds: 9223372036854775807 1\n").unwrap_err();
+        assert_eq!(
+            (error.column, error.message.as_str()),
+            (8, "the seed is too large")
+        );
+        let error = Day05::try_from("seeds: 79 -14\n").unwrap_err();
+        assert_eq!(error.message, "the length can't be negative");
     }
 
     #[test]
-    fn file_01() {
-        let mut solver: Day05 = std::fs::read_to_string("05/01").unwrap().into();
-        assert_eq!(solver.solve().unwrap(), crate::Solution::Integer(35, 46));
+    fn overlapping_map_entries() {
+        let error = map_error("50 98 2\n52 50 48\n0 98 1");
+        assert_eq!((error.line, error.column), (3, 3));
+        assert_eq!(error.message, "source start 98 is already mapped");
+        let error = map_error("50 98 2\n52 50 48\n0 97 2");
+        assert_eq!(error.message, "source range 97..99 overlaps 50..98");
+        let error = map_error("50 98 2\n52 50 48\n0 40 11");
+        assert_eq!(error.message, "source range 40..51 overlaps 50..98");
+        // Entries which only touch are fine.
+        assert!(Alminac::parse(Lines::new(5, "50 98 2\n52 50 48")).is_ok());
     }
 
     #[test]
-    fn file_input() {
-        let mut solver: Day05 = std::fs::read_to_string("05/input").unwrap().into();
+    fn identity_gaps() {
+        let alminac = Alminac::parse(Lines::new(5, "0 15 37\n37 52 2\n39 0 15\n60 60 10")).unwrap();
+        assert_eq!(alminac.gaps(), [{ 54..60 }].into_iter().collect());
+        assert_eq!(Alminac::default().gaps(), RangeSet::new());
+    }
+
+    #[test]
+    fn bad_map_headers() {
+        let input = "seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n\nsoil to water:\n";
+        let error = Day05::try_from(input).unwrap_err();
+        assert_eq!((error.line, error.column), (6, 1));
         assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(825516882, 136096660)
+            error.message,
+            "expected a map header like 'seed-to-soil map:'"
         );
+
+        let input = "seeds: 79 14\n\nseed-soil map:\n";
+        let error = Day05::try_from(input).unwrap_err();
+        assert_eq!((error.line, error.column), (3, 10));
+
+        let input = "seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n\nseed-to-soil map:\n";
+        let error = Day05::try_from(input).unwrap_err();
+        assert_eq!(error.message, "the seed-to-soil map is already given");
     }
 }
@@ -1,21 +1,59 @@
+use crate::parse::{Line, Lines, ParseError};
 use crate::puzzle;
+use crate::registry;
 use std::ops::Range;
 
-/// Solves a special case of the quadratic formula where a = -1
-/// The bounds are both rounded up to the nearest integer
-fn solve_poly_roots(b: i64, c: i64) -> Range<i64> {
-    let bf = b as f64;
-    let cf = c as f64;
-    assert!((bf * bf + 4.0 * cf) > 0.0);
-    let sqrt = (bf * bf + 4.0 * cf).sqrt();
-    let mut x1 = (-bf + sqrt) / -2.0;
-    let x2 = (-bf - sqrt) / -2.0;
-
-    if x1.floor() == x1.ceil() {
-        x1 += 1.0;
+/// Whether a race is won by beating the record distance, or by at least matching it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Inequality {
+    Strict,
+    NonStrict,
+}
+
+impl TryFrom<&str> for Inequality {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "strict" => Ok(Inequality::Strict),
+            "non-strict" => Ok(Inequality::NonStrict),
+            _ => Err(format!(
+                "Invalid inequality '{}', expected strict or non-strict.",
+                value
+            )),
+        }
+    }
+}
+
+/// Solves a special case of the quadratic formula where a = -1,
+/// returning the integers x where -x^2 + bx + c is positive, or non-negative.
+/// The arithmetic is exact, and there may be no such x, giving an empty range.
+fn solve_poly_roots(b: i128, c: i128, inequality: Inequality) -> Range<i128> {
+    // -x^2 + bx + c = (d - (2x - b)^2) / 4, so x is a solution when |2x - b| is below the root of d.
+    let d = b * b + 4 * c;
+    if d < 0 {
+        return 0..0;
     }
+    let root = d.isqrt();
+    let max_distance = match inequality {
+        Inequality::Strict if root * root == d => root - 1,
+        _ => root,
+    };
+    let start = (b - max_distance + 1).div_euclid(2);
+    let end = (b + max_distance).div_euclid(2) + 1;
+    start..end.max(start)
+}
+
+/// The times the button can be held for to win a race, which are from 0 to the whole race.
+fn ways_to_win(time: i64, record: i64, inequality: Inequality) -> Range<i128> {
+    // Negated in an i128, since the record may be the smallest i64.
+    let roots = solve_poly_roots(i128::from(time), -i128::from(record), inequality);
+    let start = roots.start.max(0);
+    start..roots.end.min(i128::from(time) + 1).max(start)
+}
 
-    x1.ceil() as i64..x2.ceil() as i64
+fn count(range: Range<i128>) -> Result<i64, Box<dyn std::error::Error>> {
+    Ok(i64::try_from(range.end - range.start)?)
 }
 
 pub struct Day06 {
@@ -23,72 +61,79 @@ pub struct Day06 {
     dists: Vec<i64>,
     p2_time: i64,
     p2_dist: i64,
+    inequality: Inequality,
 }
 
-impl Day06 {
-    fn solve_p1(&self) -> i64 {
-        self.times
-            .iter()
-            .zip(self.dists.iter())
-            .map(|(b, c)| solve_poly_roots(*b, -c))
-            .map(|r| r.end - r.start)
-            .product()
-    }
-
-    fn solve_p2(&self) -> i64 {
-        let range = solve_poly_roots(self.p2_time, -self.p2_dist);
-        range.end - range.start
-    }
+/// Reads a line like "Time:      7  15   30" as separate numbers, and as one number with the spaces removed.
+fn read_numbers(line: Line, prefix: &str) -> Result<(Vec<i64>, i64), ParseError> {
+    let numbers = line.strip_prefix(line.text(), prefix)?;
+    let joined = numbers.split_whitespace().collect::<String>();
+    let joined = joined.parse::<i64>().map_err(|e| {
+        line.error(
+            numbers.trim(),
+            format!("invalid number '{}': {}", joined, e),
+        )
+    })?;
+    Ok((line.parse_all(numbers)?, joined))
 }
 
-impl From<String> for Day06 {
-    fn from(input: String) -> Self {
-        Day06 {
-            times: input
-                .lines()
-                .next()
-                .unwrap()
-                .split_whitespace()
-                .skip(1)
-                .map(|n| n.parse::<i64>().unwrap())
-                .collect(),
-            dists: input
-                .lines()
-                .skip(1)
-                .next()
-                .unwrap()
-                .split_whitespace()
-                .skip(1)
-                .map(|n| n.parse::<i64>().unwrap())
-                .collect(),
-            p2_time: input
-                .lines()
-                .next()
-                .unwrap()
-                .strip_prefix("Time:")
-                .unwrap()
-                .split_whitespace()
-                .collect::<String>()
-                .parse::<i64>()
-                .unwrap(),
-            p2_dist: input
-                .lines()
-                .skip(1)
-                .next()
-                .unwrap()
-                .strip_prefix("Distance:")
-                .unwrap()
-                .split_whitespace()
-                .collect::<String>()
-                .parse::<i64>()
-                .unwrap(),
+pub const DAY: registry::Day = registry::Day {
+    number: 6,
+    title: "Wait For It",
+    build: |input| Ok(Box::new(Day06::try_from(input)?)),
+};
+
+impl TryFrom<&str> for Day06 {
+    type Error = ParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let mut lines = Lines::new(6, input);
+        let (times, p2_time) = read_numbers(lines.expect("the race times")?, "Time:")?;
+        let dists_line = lines.expect("the race distances")?;
+        let (dists, p2_dist) = read_numbers(dists_line, "Distance:")?;
+
+        if times.len() != dists.len() {
+            return Err(dists_line.error(
+                dists_line.text(),
+                format!("expected {} distances, one for each race", times.len()),
+            ));
         }
+
+        Ok(Day06 {
+            times,
+            dists,
+            p2_time,
+            p2_dist,
+            inequality: Inequality::Strict,
+        })
     }
 }
 
 impl puzzle::Solve for Day06 {
-    fn solve(&mut self) -> Result<puzzle::Solution, Box<dyn std::error::Error>> {
-        Ok(puzzle::Solution::Integer(self.solve_p1(), self.solve_p2()))
+    fn solve_p1(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        let mut product: i64 = 1;
+        for (time, dist) in self.times.iter().zip(self.dists.iter()) {
+            let ways = count(ways_to_win(*time, *dist, self.inequality))?;
+            product = product.checked_mul(ways).ok_or("the product overflows")?;
+        }
+        Ok(puzzle::Answer::Integer(product))
+    }
+
+    fn solve_p2(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        Ok(puzzle::Answer::Integer(count(ways_to_win(
+            self.p2_time,
+            self.p2_dist,
+            self.inequality,
+        ))?))
+    }
+
+    /// `inequality=non-strict` counts matching the record as winning.
+    fn configure(&mut self, setting: &puzzle::Setting) -> Result<(), Box<dyn std::error::Error>> {
+        match setting.key.as_str() {
+            "inequality" => self.inequality = Inequality::try_from(setting.value.as_str())?,
+            _ => return Err(format!("Unknown setting '{}'.", setting.key).into()),
+        }
+        Ok(())
     }
 }
 
@@ -99,25 +144,75 @@ mod tests {
 
     #[test]
     fn test_solve_poly_roots() {
-        assert_eq!(solve_poly_roots(15, -40), 4..12);
-        assert_eq!(solve_poly_roots(7, -9), 2..6);
+        assert_eq!(solve_poly_roots(15, -40, Inequality::Strict), 4..12);
+        assert_eq!(solve_poly_roots(7, -9, Inequality::Strict), 2..6);
+        assert_eq!(solve_poly_roots(30, -200, Inequality::Strict), 11..20);
+        assert_eq!(solve_poly_roots(30, -200, Inequality::NonStrict), 10..21);
     }
 
     #[test]
-    fn file_01() {
-        let mut solver: Day06 = std::fs::read_to_string("06/01").unwrap().into();
-        assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(288, 71503)
-        );
+    fn no_way_to_win() {
+        assert!(solve_poly_roots(4, -5, Inequality::NonStrict).is_empty());
+        assert!(solve_poly_roots(4, -4, Inequality::Strict).is_empty());
+        assert_eq!(solve_poly_roots(4, -4, Inequality::NonStrict), 2..3);
+        assert!(ways_to_win(0, 0, Inequality::Strict).is_empty());
+    }
+
+    #[test]
+    fn smallest_record() {
+        let day = Day06::try_from("Time: 1\nDistance: -9223372036854775808").unwrap();
+        assert_eq!(day.solve_p1().unwrap(), puzzle::Answer::Integer(2));
+        assert_eq!(day.solve_p2().unwrap(), puzzle::Answer::Integer(2));
     }
 
     #[test]
-    fn file_input() {
-        let mut solver: Day06 = std::fs::read_to_string("06/input").unwrap().into();
+    fn matches_brute_force() {
+        for inequality in [Inequality::Strict, Inequality::NonStrict] {
+            for time in 0..40_i64 {
+                for record in -3..time * time / 4 + 3 {
+                    let wins: Vec<i128> = (0..=time)
+                        .filter(|hold| match inequality {
+                            Inequality::Strict => hold * (time - hold) > record,
+                            Inequality::NonStrict => hold * (time - hold) >= record,
+                        })
+                        .map(i128::from)
+                        .collect();
+                    let ways = ways_to_win(time, record, inequality);
+                    assert_eq!(
+                        ways.clone().collect::<Vec<_>>(),
+                        wins,
+                        "{} {}",
+                        time,
+                        record
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn beyond_f64_precision() {
+        // The record is exactly matched by holding for 1000000003 or 3000000004, too big for an f64 to tell.
+        let (time, hold) = (4_000_000_007, 1_000_000_003);
+        let record = hold * (time - hold);
         assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(4811940, 30077773)
+            ways_to_win(time, record, Inequality::Strict),
+            1_000_000_004..3_000_000_004
         );
+        assert_eq!(
+            ways_to_win(time, record, Inequality::NonStrict),
+            1_000_000_003..3_000_000_005
+        );
+    }
+
+    #[test]
+    fn configure_inequality() {
+        let mut day = Day06::try_from("Time: 4\nDistance: 4").unwrap();
+        assert_eq!(day.solve_p1().unwrap(), puzzle::Answer::Integer(0));
+        let setting = puzzle::Setting::try_from("inequality=non-strict").unwrap();
+        day.configure(&setting).unwrap();
+        assert_eq!(day.solve_p1().unwrap(), puzzle::Answer::Integer(1));
+        let setting = puzzle::Setting::try_from("inequality=maybe").unwrap();
+        assert!(day.configure(&setting).is_err());
     }
 }
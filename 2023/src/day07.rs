@@ -1,112 +1,185 @@
-use crate::puzzle;
-use fancy_regex::Regex;
+use crate::parse::{Line, Lines, ParseError};
+use crate::puzzle::{self, Part};
+use crate::registry;
+use crate::runner;
 use itertools::Itertools;
+use std::cmp::Reverse;
 
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
-enum HandType {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
-}
-
-fn compile_hand_regex() -> Vec<Regex> {
-    vec![
-        Regex::new(r"(.)\1{4}").unwrap(),
-        Regex::new(r"(.)\1{3}").unwrap(),
-        Regex::new(r"(.)\1{2}(.)\2").unwrap(),
-        Regex::new(r"(.)\1(.)\2{2}").unwrap(),
-        Regex::new(r"(.)\1{2}").unwrap(),
-        Regex::new(r"(.)\1.?(.)\2").unwrap(),
-        Regex::new(r"(.)\1").unwrap(),
-    ]
+/// A rung of the hand-type ladder: the counts of equal cards a hand needs, largest first.
+/// A hand with more cards of a kind than needed still counts, so "AAAKK" is also two pair.
+#[derive(Debug)]
+struct HandType {
+    name: &'static str,
+    counts: &'static [usize],
 }
 
-impl From<&str> for HandType {
-    fn from(hand: &str) -> Self {
-        HandType::new(hand, compile_hand_regex())
-    }
-}
+const STANDARD_HAND_TYPES: &[HandType] = &[
+    HandType {
+        name: "high card",
+        counts: &[1],
+    },
+    HandType {
+        name: "one pair",
+        counts: &[2],
+    },
+    HandType {
+        name: "two pair",
+        counts: &[2, 2],
+    },
+    HandType {
+        name: "three of a kind",
+        counts: &[3],
+    },
+    HandType {
+        name: "full house",
+        counts: &[3, 2],
+    },
+    HandType {
+        name: "four of a kind",
+        counts: &[4],
+    },
+    HandType {
+        name: "five of a kind",
+        counts: &[5],
+    },
+];
 
 impl HandType {
-    fn new(hand_in: &str, regex: Vec<Regex>) -> Self {
-        assert!(hand_in.len() == 5);
-        let hand = hand_in.chars().sorted().collect::<String>();
-
-        if regex[0].is_match(&hand).unwrap() {
-            HandType::FiveOfAKind
-        } else if regex[1].is_match(&hand).unwrap() {
-            HandType::FourOfAKind
-        } else if regex[2].is_match(&hand).unwrap() || regex[3].is_match(&hand).unwrap() {
-            HandType::FullHouse
-        } else if regex[4].is_match(&hand).unwrap() {
-            HandType::ThreeOfAKind
-        } else if regex[5].is_match(&hand).unwrap() {
-            HandType::TwoPair
-        } else if regex[6].is_match(&hand).unwrap() {
-            HandType::OnePair
-        } else {
-            HandType::HighCard
-        }
+    /// Whether cards with these counts of equal cards, largest first, make this type.
+    fn is_made_by(&self, counts: &[usize]) -> bool {
+        self.counts
+            .iter()
+            .enumerate()
+            .all(|(i, needed)| counts.get(i).is_some_and(|count| count >= needed))
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
-enum Card {
-    Joker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    Jack,
-    Queen,
-    King,
-    Ace,
+/// The rules of a game of Camel Cards, so variants of the game are data rather than code.
+#[derive(Debug)]
+struct Rules {
+    hand_size: usize,
+    /// Every card, from weakest to strongest, which breaks ties between hands of the same type.
+    order: &'static str,
+    /// Cards which stand for whatever card gives the hand the best type, but keep their place in the order.
+    wild: &'static str,
+    /// The hand types from weakest to strongest. A hand has the strongest type it makes.
+    hand_types: &'static [HandType],
 }
 
-impl From<u8> for Card {
-    fn from(n: u8) -> Self {
-        match n {
-            b'A' => Card::Ace,
-            b'K' => Card::King,
-            b'Q' => Card::Queen,
-            b'J' => Card::Jack,
-            b'T' => Card::Ten,
-            b'9' => Card::Nine,
-            b'8' => Card::Eight,
-            b'7' => Card::Seven,
-            b'6' => Card::Six,
-            b'5' => Card::Five,
-            b'4' => Card::Four,
-            b'3' => Card::Three,
-            b'2' => Card::Two,
-            c @ _ => panic!("Invalid u8 given, cannot create a Card from {}", c),
+const PART1_RULES: Rules = Rules {
+    hand_size: 5,
+    order: "23456789TJQKA",
+    wild: "",
+    hand_types: STANDARD_HAND_TYPES,
+};
+
+/// Part 2 turns each 'J' into a joker, which is wild but the weakest card on its own.
+const PART2_RULES: Rules = Rules {
+    hand_size: 5,
+    order: "J23456789TQKA",
+    wild: "J",
+    hand_types: STANDARD_HAND_TYPES,
+};
+
+impl Rules {
+    /// The strength of a card, counting up from 0 for the weakest.
+    fn strength(&self, card: char) -> Option<usize> {
+        self.order.chars().position(|c| c == card)
+    }
+
+    /// The position in the ladder of the strongest type the cards make, if they make any,
+    /// and the cards with each wild card replaced by a card it could stand for to make that type.
+    fn hand_type(&self, cards: &str) -> Option<(usize, String)> {
+        let (wild, plain): (Vec<char>, Vec<char>) =
+            cards.chars().partition(|&c| self.wild.contains(c));
+        // The most common and then strongest kinds first, which wild cards copy when it makes no difference.
+        let kinds: Vec<(char, usize)> = plain
+            .into_iter()
+            .counts()
+            .into_iter()
+            .sorted_by_key(|&(c, count)| (Reverse(count), Reverse(self.strength(c))))
+            .collect();
+        let mut counts: Vec<usize> = kinds.iter().map(|&(_, count)| count).collect();
+        let mut best = None;
+        self.best_hand_type(&mut counts, wild.len(), &mut best);
+        let (hand_type, counts) = best?;
+
+        // Wild cards making a new kind stand for the strongest cards not in the hand.
+        let mut unused = self
+            .order
+            .chars()
+            .rev()
+            .filter(|&c| !cards.contains(c) && !self.wild.contains(c));
+        let mut stand_ins = vec![];
+        for (i, &count) in counts.iter().enumerate() {
+            let (card, had) = match kinds.get(i) {
+                Some(&(card, had)) => (Some(card), had),
+                None => (unused.next(), 0),
+            };
+            stand_ins.extend(std::iter::repeat_n(card, count - had));
         }
+        let mut stand_ins = stand_ins.into_iter();
+        let effective = cards
+            .chars()
+            .map(|c| match self.wild.contains(c) {
+                true => stand_ins.next().flatten().unwrap_or(c),
+                false => c,
+            })
+            .collect();
+        Some((hand_type, effective))
+    }
+
+    /// Tries every way of adding the wild cards to the counts of equal cards, or as new kinds of card,
+    /// keeping the first counts found which make the strongest type.
+    /// Adding them all to the largest count is best for the standard ladder, but not for every ladder.
+    fn best_hand_type(
+        &self,
+        counts: &mut Vec<usize>,
+        wild: usize,
+        best: &mut Option<(usize, Vec<usize>)>,
+    ) {
+        if wild == 0 {
+            let sorted: Vec<usize> = counts
+                .iter()
+                .copied()
+                .sorted_unstable_by(|a, b| b.cmp(a))
+                .collect();
+            let hand_type = self.hand_types.iter().rposition(|t| t.is_made_by(&sorted));
+            if let Some(hand_type) = hand_type {
+                if best.as_ref().is_none_or(|(best, _)| hand_type > *best) {
+                    *best = Some((hand_type, counts.clone()));
+                }
+            }
+            return;
+        }
+        for i in 0..counts.len() {
+            counts[i] += 1;
+            self.best_hand_type(counts, wild - 1, best);
+            counts[i] -= 1;
+        }
+        counts.push(1);
+        self.best_hand_type(counts, wild - 1, best);
+        counts.pop();
     }
 }
 
-#[derive(Debug, Eq)]
+#[derive(Clone, Debug, Eq)]
 struct Hand {
-    cards: [Card; 5],
+    cards: String,
+    /// The cards with each wild card replaced by the card it stands for.
+    effective: String,
+    /// The strength of each card, to break ties between hands of the same type.
+    strengths: Vec<usize>,
     bid: i64,
-    hand_type: HandType,
+    /// The position of the hand's type in the ladder of the rules it was dealt under.
+    hand_type: usize,
 }
 
 impl Ord for Hand {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        if self.hand_type == other.hand_type {
-            self.cards.cmp(&other.cards)
-        } else {
-            self.hand_type.cmp(&other.hand_type)
-        }
+        self.hand_type
+            .cmp(&other.hand_type)
+            .then_with(|| self.strengths.cmp(&other.strengths))
     }
 }
 
@@ -118,79 +191,164 @@ impl PartialOrd for Hand {
 
 impl PartialEq for Hand {
     fn eq(&self, other: &Self) -> bool {
-        self.hand_type == other.hand_type && self.cards == other.cards
-    }
-}
-
-impl From<&str> for Hand {
-    fn from(line: &str) -> Self {
-        Hand::new(line, compile_hand_regex())
+        self.cmp(other).is_eq()
     }
 }
 
 impl Hand {
-    fn new(line: &str, regex: Vec<Regex>) -> Self {
-        let mut splits = line.split_whitespace();
-        let cards_str = splits.next().unwrap();
-        let cards_slice = cards_str.as_bytes();
-
-        let mut cards: [Card; 5] = [Card::Two; 5];
-        cards[0] = Card::from(*cards_slice.get(0).unwrap());
-        cards[1] = Card::from(*cards_slice.get(1).unwrap());
-        cards[2] = Card::from(*cards_slice.get(2).unwrap());
-        cards[3] = Card::from(*cards_slice.get(3).unwrap());
-        cards[4] = Card::from(*cards_slice.get(4).unwrap());
-
-        Hand {
-            cards,
-            bid: splits.next().unwrap().parse::<i64>().unwrap(),
-            hand_type: HandType::new(cards_str, regex),
-        }
-    }
+    fn new(line: Line, rules: &Rules) -> Result<Self, ParseError> {
+        let expected_size = format!("expected a hand of {} cards", rules.hand_size);
+        let mut splits = line.text().split_whitespace();
+        let cards = splits
+            .next()
+            .ok_or_else(|| line.error(line.text(), &expected_size))?;
 
-    fn assign_p2_hand_type(self: &mut Self) {
-        for card in &mut self.cards {
-            if *card == Card::Jack {
-                *card = Card::Joker;
-            }
-            // TODO: Do some magic here to re-score the hands. I really don't know how to begin...
+        let mut strengths = Vec::with_capacity(rules.hand_size);
+        for (i, c) in cards.char_indices() {
+            let strength = rules.strength(c).ok_or_else(|| {
+                line.error(&cards[i..i + c.len_utf8()], format!("invalid card '{}'", c))
+            })?;
+            strengths.push(strength);
         }
+        if strengths.len() != rules.hand_size {
+            return Err(line.error(cards, expected_size));
+        }
+        let (hand_type, effective) = rules
+            .hand_type(cards)
+            .ok_or_else(|| line.error(cards, "the hand is none of the hand types"))?;
+
+        let bid = splits
+            .next()
+            .ok_or_else(|| line.missing_after(cards, "expected a bid"))?;
+
+        Ok(Hand {
+            cards: String::from(cards),
+            effective,
+            strengths,
+            bid: line.parse::<i64>(bid)?,
+            hand_type,
+        })
     }
 }
 
 #[derive(Debug)]
 pub struct Day07 {
     hands: Vec<Hand>,
+    joker_hands: Vec<Hand>,
 }
 
-impl From<String> for Day07 {
-    fn from(input: String) -> Self {
-        let regex = compile_hand_regex();
-        Day07 {
-            hands: input.lines().map(|l| Hand::new(l, regex.clone())).collect(),
-        }
+pub const DAY: registry::Day = registry::Day {
+    number: 7,
+    title: "Camel Cards",
+    build: |input| Ok(Box::new(Day07::try_from(input)?)),
+};
+
+impl TryFrom<&str> for Day07 {
+    type Error = ParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let deal = |rules: &Rules| -> Result<Vec<Hand>, ParseError> {
+            Lines::new(7, input).map(|l| Hand::new(l, rules)).collect()
+        };
+        Ok(Day07 {
+            hands: deal(&PART1_RULES)?,
+            joker_hands: deal(&PART2_RULES)?,
+        })
+    }
+}
+
+/// How a hand beats the hand ranked just below it: by type, or by the first card that differs.
+fn tie_break(hand: &Hand, below: &Hand, rules: &Rules) -> String {
+    if hand.hand_type != below.hand_type {
+        let name = |hand: &Hand| rules.hand_types[hand.hand_type].name;
+        return format!("{} over {}", name(hand), name(below));
+    }
+    let cards = std::iter::zip(hand.cards.chars(), below.cards.chars());
+    match std::iter::zip(1.., cards).find(|(_, (card, other))| card != other) {
+        Some((i, (card, other))) => format!("card {}: {} over {}", i, card, other),
+        None => String::from("tied, so input order"),
     }
 }
 
 impl Day07 {
-    fn solve_p1(&mut self) -> i64 {
-        self.hands.sort();
-        std::iter::zip(self.hands.iter(), 1..)
-            .map(|(h, i)| i * h.bid)
-            .sum()
+    fn hands(&self, part: Part) -> (&[Hand], &Rules) {
+        match part {
+            Part::One => (&self.hands, &PART1_RULES),
+            Part::Two => (&self.joker_hands, &PART2_RULES),
+        }
     }
 
-    fn solve_p2(&mut self) -> i64 {
-        for hand in &mut self.hands {
-            hand.assign_p2_hand_type();
+    /// A table of the hands from the weakest to the strongest, showing how each earned its rank.
+    fn explain(&self, part: Part) -> String {
+        let (hands, rules) = self.hands(part);
+        let hands: Vec<&Hand> = hands.iter().sorted().collect();
+        let mut total = 0;
+        let mut rows = vec![];
+        for (i, (hand, rank)) in std::iter::zip(&hands, 1..).enumerate() {
+            total += rank * hand.bid;
+            rows.push(vec![
+                rank.to_string(),
+                hand.cards.clone(),
+                String::from(rules.hand_types[hand.hand_type].name),
+                hand.effective.clone(),
+                hand.bid.to_string(),
+                (rank * hand.bid).to_string(),
+                match i.checked_sub(1) {
+                    Some(below) => tie_break(hand, hands[below], rules),
+                    None => String::new(),
+                },
+            ]);
         }
-        self.solve_p1()
+        let headers = [
+            "Rank",
+            "Hand",
+            "Type",
+            "Plays as",
+            "Bid",
+            "Winnings",
+            "Beats the hand below",
+        ];
+        format!(
+            "{}:\n{}\ntotal winnings: {}",
+            part,
+            runner::format_columns(&headers, &rows),
+            total
+        )
+    }
+
+    fn total_winnings(mut hands: Vec<Hand>) -> i64 {
+        hands.sort();
+        std::iter::zip(hands.iter(), 1..)
+            .map(|(h, i)| i * h.bid)
+            .sum()
     }
 }
 
 impl puzzle::Solve for Day07 {
-    fn solve(&mut self) -> Result<puzzle::Solution, Box<dyn std::error::Error>> {
-        Ok(puzzle::Solution::Integer(self.solve_p1(), self.solve_p2()))
+    fn solve_p1(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        Ok(puzzle::Answer::Integer(Day07::total_winnings(
+            self.hands.clone(),
+        )))
+    }
+
+    fn solve_p2(&self) -> Result<puzzle::Answer, Box<dyn std::error::Error>> {
+        Ok(puzzle::Answer::Integer(Day07::total_winnings(
+            self.joker_hands.clone(),
+        )))
+    }
+
+    fn query(&self, args: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+        match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["explain", ref part @ ..] => {
+                let parts = match part {
+                    [part] => vec![Part::try_from(*part)?],
+                    _ => vec![Part::One, Part::Two],
+                };
+                let tables: Vec<String> = parts.into_iter().map(|p| self.explain(p)).collect();
+                Ok(tables.join("\n\n"))
+            }
+            _ => Err("Expected 'explain [1|2]'.".into()),
+        }
     }
 }
 
@@ -199,60 +357,57 @@ mod tests {
     use super::*;
     use crate::puzzle::Solve;
 
-    #[test]
-    fn create_card_from_char() {
-        assert_eq!(Card::from(b'A'), Card::Ace);
-        assert_eq!(Card::from(b'K'), Card::King);
-        assert_eq!(Card::from(b'Q'), Card::Queen);
-        assert_eq!(Card::from(b'J'), Card::Jack);
-        assert_eq!(Card::from(b'T'), Card::Ten);
-        assert_eq!(Card::from(b'9'), Card::Nine);
-        assert_eq!(Card::from(b'8'), Card::Eight);
-        assert_eq!(Card::from(b'7'), Card::Seven);
-        assert_eq!(Card::from(b'6'), Card::Six);
-        assert_eq!(Card::from(b'5'), Card::Five);
-        assert_eq!(Card::from(b'4'), Card::Four);
-        assert_eq!(Card::from(b'3'), Card::Three);
-        assert_eq!(Card::from(b'2'), Card::Two);
+    fn hand(line: &str) -> Hand {
+        Hand::new(Lines::new(7, line).next().unwrap(), &PART1_RULES).unwrap()
+    }
+
+    fn hand_type(rules: &Rules, cards: &str) -> &'static str {
+        rules.hand_types[rules.hand_type(cards).unwrap().0].name
     }
 
     #[test]
-    #[should_panic]
-    fn create_card_from_invalid_char() {
-        _ = Card::from(b'x');
+    fn card_order() {
+        let order: Vec<usize> = "23456789TJQKA"
+            .chars()
+            .map(|c| PART1_RULES.strength(c).unwrap())
+            .collect();
+        assert_eq!(order, (0..13).collect::<Vec<_>>());
+        assert_eq!(PART2_RULES.strength('J'), Some(0));
+        assert_eq!(PART2_RULES.strength('2'), Some(1));
+        assert_eq!(PART1_RULES.strength('x'), None);
     }
 
     #[test]
     fn create_hand_type_from_cards() {
-        assert_eq!(HandType::from("AAAAA"), HandType::FiveOfAKind);
-        assert_eq!(HandType::from("AA8AA"), HandType::FourOfAKind);
-        assert_eq!(HandType::from("23332"), HandType::FullHouse);
-        assert_eq!(HandType::from("TTT98"), HandType::ThreeOfAKind);
-        assert_eq!(HandType::from("23432"), HandType::TwoPair);
-        assert_eq!(HandType::from("A23A4"), HandType::OnePair);
-        assert_eq!(HandType::from("23456"), HandType::HighCard);
+        assert_eq!(hand_type(&PART1_RULES, "AAAAA"), "five of a kind");
+        assert_eq!(hand_type(&PART1_RULES, "AA8AA"), "four of a kind");
+        assert_eq!(hand_type(&PART1_RULES, "23332"), "full house");
+        assert_eq!(hand_type(&PART1_RULES, "TTT98"), "three of a kind");
+        assert_eq!(hand_type(&PART1_RULES, "23432"), "two pair");
+        assert_eq!(hand_type(&PART1_RULES, "A23A4"), "one pair");
+        assert_eq!(hand_type(&PART1_RULES, "23456"), "high card");
     }
 
     #[test]
     fn comapre_hands() {
         assert_eq!(
-            Hand::from("QQQJA 483").cmp(&Hand::from("KK677 28")),
+            hand("QQQJA 483").cmp(&hand("KK677 28")),
             std::cmp::Ordering::Greater
         );
         assert_eq!(
-            Hand::from("QQQJA 483").cmp(&Hand::from("QQQJA 28")),
+            hand("QQQJA 483").cmp(&hand("QQQJA 28")),
             std::cmp::Ordering::Equal
         );
         assert_eq!(
-            Hand::from("KK677 483").cmp(&Hand::from("KTJJT 28")),
+            hand("KK677 483").cmp(&hand("KTJJT 28")),
             std::cmp::Ordering::Greater
         );
         assert_eq!(
-            Hand::from("32T3K 765").cmp(&Hand::from("T55J5 684")),
+            hand("32T3K 765").cmp(&hand("T55J5 684")),
             std::cmp::Ordering::Less
         );
         assert_eq!(
-            Hand::from("T55J5 684").cmp(&Hand::from("QQQJA 483")),
+            hand("T55J5 684").cmp(&hand("QQQJA 483")),
             std::cmp::Ordering::Less
         );
     }
@@ -261,40 +416,195 @@ mod tests {
     fn sort_hands() {
         assert_eq!(
             vec![
-                Hand::from("32T3K 765"),
-                Hand::from("T55J5 684"),
-                Hand::from("KK677 28"),
-                Hand::from("KTJJT 220"),
-                Hand::from("QQQJA 483"),
+                hand("32T3K 765"),
+                hand("T55J5 684"),
+                hand("KK677 28"),
+                hand("KTJJT 220"),
+                hand("QQQJA 483"),
             ]
             .into_iter()
             .sorted()
             .collect::<Vec<_>>(),
             vec![
-                Hand::from("32T3K 765"),
-                Hand::from("KTJJT 220"),
-                Hand::from("KK677 28"),
-                Hand::from("T55J5 684"),
-                Hand::from("QQQJA 483"),
+                hand("32T3K 765"),
+                hand("KTJJT 220"),
+                hand("KK677 28"),
+                hand("T55J5 684"),
+                hand("QQQJA 483"),
             ]
         );
     }
 
+    fn joker_hand(line: &str) -> Hand {
+        Hand::new(Lines::new(7, line).next().unwrap(), &PART2_RULES).unwrap()
+    }
+
+    #[test]
+    fn hand_types_with_jokers() {
+        assert_eq!(hand_type(&PART2_RULES, "JJJJJ"), "five of a kind");
+        assert_eq!(hand_type(&PART2_RULES, "JJJJ2"), "five of a kind");
+        assert_eq!(hand_type(&PART2_RULES, "KTJJT"), "four of a kind");
+        assert_eq!(hand_type(&PART2_RULES, "T55J5"), "four of a kind");
+        assert_eq!(hand_type(&PART2_RULES, "2345J"), "one pair");
+        assert_eq!(hand_type(&PART2_RULES, "2233J"), "full house");
+        assert_eq!(hand_type(&PART2_RULES, "32T3K"), "one pair");
+    }
+
+    #[test]
+    fn jokers_break_ties_lowest() {
+        assert_eq!(
+            joker_hand("JJJJJ 1").cmp(&joker_hand("22222 1")),
+            std::cmp::Ordering::Less
+        );
+        assert_eq!(
+            joker_hand("JKKK2 1").cmp(&joker_hand("QQQQ2 1")),
+            std::cmp::Ordering::Less
+        );
+        assert_eq!(
+            joker_hand("J2222 1").cmp(&joker_hand("2222J 1")),
+            std::cmp::Ordering::Less
+        );
+    }
+
+    #[test]
+    fn seven_card_variant() {
+        const HAND_TYPES: &[HandType] = &[
+            HandType {
+                name: "high card",
+                counts: &[1],
+            },
+            HandType {
+                name: "one pair",
+                counts: &[2],
+            },
+            HandType {
+                name: "two pair",
+                counts: &[2, 2],
+            },
+            HandType {
+                name: "three of a kind",
+                counts: &[3],
+            },
+            HandType {
+                name: "three pair",
+                counts: &[2, 2, 2],
+            },
+            HandType {
+                name: "full house",
+                counts: &[3, 2],
+            },
+            HandType {
+                name: "four of a kind",
+                counts: &[4],
+            },
+        ];
+        let rules = Rules {
+            hand_size: 7,
+            order: "23456789TJQKA",
+            wild: "2",
+            hand_types: HAND_TYPES,
+        };
+        assert_eq!(hand_type(&rules, "AKQJT98"), "high card");
+        assert_eq!(hand_type(&rules, "AKQJT92"), "one pair");
+        assert_eq!(hand_type(&rules, "AAKKQQJ"), "three pair");
+        assert_eq!(hand_type(&rules, "AAKKQ22"), "four of a kind");
+        assert_eq!(hand_type(&rules, "2222222"), "four of a kind");
+
+        let day = Lines::new(7, "AAKKQQJ 1\nAAKKQQ 1");
+        let hands: Vec<_> = day.map(|l| Hand::new(l, &rules)).collect();
+        assert!(hands[0].is_ok());
+        assert_eq!(
+            hands[1].as_ref().unwrap_err().message,
+            "expected a hand of 7 cards"
+        );
+    }
+
+    #[test]
+    fn wild_cards_make_the_best_type() {
+        // Adding both wild cards to the pair makes four of a kind, but three pair is the stronger type here.
+        const HAND_TYPES: &[HandType] = &[
+            HandType {
+                name: "high card",
+                counts: &[1],
+            },
+            HandType {
+                name: "four of a kind",
+                counts: &[4],
+            },
+            HandType {
+                name: "three pair",
+                counts: &[2, 2, 2],
+            },
+        ];
+        let rules = Rules {
+            hand_size: 6,
+            order: "23456789TJQKA",
+            wild: "2",
+            hand_types: HAND_TYPES,
+        };
+        assert_eq!(hand_type(&rules, "AAKQ22"), "three pair");
+        assert_eq!(hand_type(&rules, "AAAK22"), "four of a kind");
+    }
+
+    #[test]
+    fn hands_without_a_type() {
+        let rules = Rules {
+            hand_types: &STANDARD_HAND_TYPES[1..],
+            ..PART1_RULES
+        };
+        assert_eq!(rules.hand_type("AAKQJ"), Some((0, String::from("AAKQJ"))));
+        assert_eq!(rules.hand_type("AKQJT"), None);
+        let line = Lines::new(7, "AKQJT 1").next().unwrap();
+        assert_eq!(
+            Hand::new(line, &rules).unwrap_err().message,
+            "the hand is none of the hand types"
+        );
+    }
+
     #[test]
-    fn file_01() {
-        let mut solver: Day07 = std::fs::read_to_string("07/01").unwrap().into();
+    fn invalid_hands() {
+        let error = Day07::try_from("32T3K 765\nT55X5 684").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 4, "X"));
+        let error = Day07::try_from("32T3 765").unwrap_err();
+        assert_eq!(error.message, "expected a hand of 5 cards");
+        let error = Day07::try_from("32T3K").unwrap_err();
         assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(6440, 5905)
+            (error.column, error.message.as_str()),
+            (6, "expected a bid")
         );
     }
 
     #[test]
-    fn file_input() {
-        let mut solver: Day07 = std::fs::read_to_string("07/input").unwrap().into();
+    fn effective_cards() {
+        let effective = |cards: &str| PART2_RULES.hand_type(cards).unwrap().1;
+        assert_eq!(effective("KTJJT"), "KTTTT");
+        assert_eq!(effective("KQJ2J"), "KQK2K");
+        assert_eq!(effective("JJJJJ"), "AAAAA");
+        assert_eq!(effective("AAAAJ"), "AAAAA");
+        assert_eq!(effective("32T3K"), "32T3K");
+        assert_eq!(PART1_RULES.hand_type("KTJJT").unwrap().1, "KTJJT");
+    }
+
+    #[test]
+    fn explain_ranking() {
+        let input = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/07/01")).unwrap();
+        let day = Day07::try_from(input.as_str()).unwrap();
+        let explained = day
+            .query(&[String::from("explain"), String::from("2")])
+            .unwrap();
+        let lines: Vec<&str> = explained.lines().collect();
+        assert_eq!(lines[0], "part2:");
         assert_eq!(
-            solver.solve().unwrap(),
-            crate::Solution::Integer(248559379, 0)
+            lines[5],
+            "3     T55J5  four of a kind  T5555     684  2052      four of a kind over two pair"
         );
+        assert_eq!(
+            lines[7],
+            "5     KTJJT  four of a kind  KTTTT     220  1100      card 1: K over Q"
+        );
+        assert_eq!(lines[8], "total winnings: 5905");
+
+        let tied = Day07::try_from("23456 1\n23456 2").unwrap();
+        assert!(tied.explain(Part::One).contains("tied, so input order"));
     }
 }
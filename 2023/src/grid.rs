@@ -0,0 +1,252 @@
+use crate::parse::{Lines, ParseError};
+use std::fmt;
+use std::ops::{Index, IndexMut, Range};
+
+/// A cell's (row, column), counted from the top left.
+pub type Position = (usize, usize);
+
+/// Steps to the four orthogonal neighbors, as (row, column) offsets.
+#[allow(dead_code)]
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Steps to all eight neighbors, diagonals included, in reading order.
+#[allow(dead_code)]
+const ALL_AROUND: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// A rectangular grid of cells, as many puzzles give their input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Parses one cell per character, one row per line. Every row must be as long as the first,
+    /// and `cell` returns None for characters which aren't allowed.
+    pub fn parse(
+        day: u8,
+        input: &str,
+        cell: impl Fn(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let mut lines = Lines::new(day, input);
+        let first = lines.expect("a row of the grid")?;
+        let width = first.text().chars().count();
+        if width == 0 {
+            return Err(first.error(
+                first.text(),
+                "expected a row of the grid, found an empty line",
+            ));
+        }
+        let mut cells = vec![];
+        let mut height = 0;
+
+        for line in std::iter::once(first).chain(lines) {
+            for (col, c) in line.text().char_indices() {
+                match cell(c) {
+                    Some(value) => cells.push(value),
+                    None => {
+                        let text = &line.text()[col..col + c.len_utf8()];
+                        return Err(line.error(text, format!("unexpected character '{}'", c)));
+                    }
+                }
+            }
+            if line.text().chars().count() != width {
+                return Err(line.error(
+                    line.text(),
+                    format!("expected a row {} characters long", width),
+                ));
+            }
+            height += 1;
+        }
+
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[allow(dead_code)]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (row, col): Position) -> bool {
+        row < self.height && col < self.width
+    }
+
+    pub fn get(&self, pos: Position) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.cells[pos.0 * self.width + pos.1])
+    }
+
+    pub fn get_mut(&mut self, pos: Position) -> Option<&mut T> {
+        match self.contains(pos) {
+            true => Some(&mut self.cells[pos.0 * self.width + pos.1]),
+            false => None,
+        }
+    }
+
+    /// The position one step away from `pos`, if it's on the grid.
+    #[allow(dead_code)]
+    pub fn step(&self, (row, col): Position, (d_row, d_col): (isize, isize)) -> Option<Position> {
+        let pos = (
+            row.checked_add_signed(d_row)?,
+            col.checked_add_signed(d_col)?,
+        );
+        self.contains(pos).then_some(pos)
+    }
+
+    /// The positions of the up to four cells sharing an edge with `pos`.
+    #[allow(dead_code)]
+    pub fn neighbors4(&self, pos: Position) -> impl Iterator<Item = Position> + '_ {
+        ORTHOGONAL
+            .iter()
+            .filter_map(move |&delta| self.step(pos, delta))
+    }
+
+    /// The positions of the up to eight cells sharing an edge or a corner with `pos`.
+    #[allow(dead_code)]
+    pub fn neighbors8(&self, pos: Position) -> impl Iterator<Item = Position> + '_ {
+        ALL_AROUND
+            .iter()
+            .filter_map(move |&delta| self.step(pos, delta))
+    }
+
+    /// Every position, in reading order.
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| (row, col)))
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    #[allow(dead_code)]
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.width, "column {} is off the grid", col);
+        self.cells.iter().skip(col).step_by(self.width)
+    }
+
+    /// The cells in a rectangle of rows and columns, in reading order.
+    /// The rectangle is clipped to the grid, so it may reach past the edges.
+    pub fn region(
+        &self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> impl Iterator<Item = (Position, &T)> {
+        let rows = rows.start..rows.end.min(self.height);
+        let cols = cols.start..cols.end.min(self.width);
+        rows.flat_map(move |row| {
+            cols.clone()
+                .map(move |col| ((row, col), &self.cells[row * self.width + col]))
+        })
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Position) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{:?} is off the grid", pos))
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, pos: Position) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is off the grid", pos))
+    }
+}
+
+/// Prints one line per row, without a newline after the last.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid<char> {
+        Grid::parse(0, "abc\ndef\n", Some).unwrap()
+    }
+
+    #[test]
+    fn parse_grid() {
+        let grid = grid();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get((1, 2)), Some(&'f'));
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(grid.get((0, 3)), None);
+        assert_eq!(grid.to_string(), "abc\ndef");
+    }
+
+    #[test]
+    fn parse_bad_grids() {
+        let error = Grid::parse(0, "abc\nde", Some).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        let error = Grid::parse(0, "12\n3x", |c| c.to_digit(10)).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 2));
+        assert_eq!(error.message, "unexpected character 'x'");
+        let error = Grid::parse(0, "\n", Some).unwrap_err();
+        assert_eq!((error.line, error.column), (1, 1));
+        assert!(Grid::parse(0, "\nabc", Some).is_err());
+    }
+
+    #[test]
+    fn neighbors() {
+        let grid = grid();
+        assert_eq!(
+            grid.neighbors4((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(
+            grid.neighbors8((0, 1)).collect::<Vec<_>>(),
+            vec![(0, 0), (0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn views() {
+        let grid = grid();
+        assert_eq!(grid.row(1), &['d', 'e', 'f']);
+        assert_eq!(grid.column(2).collect::<String>(), "cf");
+        assert_eq!(
+            grid.region(1..5, 1..3).map(|(_, c)| c).collect::<String>(),
+            "ef"
+        );
+        assert_eq!(grid.positions().nth(4), Some((1, 1)));
+    }
+}
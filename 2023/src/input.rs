@@ -0,0 +1,113 @@
+use crate::registry::Day;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The environment variable naming the directory which holds the day directories ("01", "02", ...).
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+static INPUT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Overrides the input directory for the rest of the run, as given on the command line.
+pub fn set_input_dir(dir: PathBuf) {
+    _ = INPUT_DIR.set(dir);
+}
+
+/// The directory holding the day directories.
+/// Set with --input-dir or AOC_INPUT_DIR, otherwise the crate directory so the runner and tests work from anywhere.
+pub fn input_dir() -> &'static Path {
+    INPUT_DIR.get_or_init(|| match std::env::var_os(INPUT_DIR_VAR) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")),
+    })
+}
+
+/// Where a day's puzzle input is read from.
+#[derive(Debug, PartialEq)]
+pub enum Source {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Source {
+    /// Resolves an input argument: "-" reads stdin, a path with a directory in it is used as given,
+    /// and a bare name is looked up in the day's directory under the input directory,
+    /// falling back to the current directory only when the day has no such file.
+    pub fn resolve(day: &Day, input: &str) -> Self {
+        let path = Path::new(input);
+        if input == "-" {
+            return Source::Stdin;
+        }
+        if path.is_absolute() || path.components().count() > 1 {
+            return Source::File(path.to_path_buf());
+        }
+        let in_day = day.input_path(input);
+        if !in_day.is_file() && path.is_file() {
+            Source::File(path.to_path_buf())
+        } else {
+            Source::File(in_day)
+        }
+    }
+
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            Source::Stdin => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                Ok(input)
+            }
+            Source::File(path) => std::fs::read_to_string(path),
+        }
+    }
+
+    /// The name used for this input in messages.
+    pub fn name(&self) -> String {
+        match self {
+            Source::Stdin => String::from("<stdin>"),
+            Source::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_inputs() {
+        let day = &crate::day05::DAY;
+        assert_eq!(Source::resolve(day, "-"), Source::Stdin);
+        assert_eq!(
+            Source::resolve(day, "01"),
+            Source::File(input_dir().join("05").join("01"))
+        );
+        assert_eq!(
+            Source::resolve(day, "/tmp/puzzle"),
+            Source::File(PathBuf::from("/tmp/puzzle"))
+        );
+        assert_eq!(
+            Source::resolve(day, "06/01"),
+            Source::File(PathBuf::from("06/01"))
+        );
+    }
+
+    #[test]
+    fn bare_names_prefer_the_day_directory() {
+        // Tests run from the crate directory, which holds Cargo.toml but no file named like an input.
+        let day = &crate::day05::DAY;
+        assert_eq!(
+            Source::resolve(day, "Cargo.toml"),
+            Source::File(PathBuf::from("Cargo.toml"))
+        );
+        assert_eq!(
+            Source::resolve(day, "missing"),
+            Source::File(input_dir().join("05").join("missing"))
+        );
+    }
+
+    #[test]
+    fn read_input_file() {
+        let source = Source::resolve(&crate::day06::DAY, "01");
+        assert!(source.read().unwrap().starts_with("Time:"));
+    }
+}
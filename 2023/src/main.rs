@@ -1,3 +1,5 @@
+mod answers;
+mod bench;
 mod day01;
 mod day02;
 mod day03;
@@ -5,47 +7,208 @@ mod day04;
 mod day05;
 mod day06;
 mod day07;
+mod grid;
+mod input;
+mod parse;
 mod puzzle;
+mod range_set;
+mod registry;
+mod report;
+mod runner;
+mod watch;
 
-use crate::day01::Day01;
-use crate::day02::Day02;
-use crate::day03::Day03;
-use crate::day04::Day04;
-use crate::day05::Day05;
-use crate::day06::Day06;
-use crate::day07::Day07;
 use crate::puzzle::*;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut args = std::env::args();
+registry::days!(day01, day02, day03, day04, day05, day06, day07);
+
+/// Removes "--name value" from the arguments, returning the value if the option was given.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            let value = args
+                .get(i + 1)
+                .cloned()
+                .ok_or(format!("{} needs a value.", name))?;
+            args.drain(i..=i + 1);
+            Ok(Some(value))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Removes "--name" from the arguments, returning whether it was given.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let len = args.len();
+    args.retain(|a| a != name);
+    args.len() != len
+}
 
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // The first argument is the program name
-    _ = args.next();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
 
-    let day = args
-        .next()
-        .expect("Please specify the day with two digits.");
+    // --part 1|2 solves only the given part, otherwise both parts are solved.
+    let part = match take_option(&mut args, "--part")? {
+        Some(value) => Some(Part::try_from(value.as_str())?),
+        None => None,
+    };
+
+    // --set key=value changes how one day is solved, and may be given more than once.
+    let mut settings = vec![];
+    while let Some(setting) = take_option(&mut args, "--set")? {
+        settings.push(Setting::try_from(setting.as_str())?);
+    }
+
+    // Options for bench mode.
+    let warmup = take_option(&mut args, "--warmup")?.map_or(Ok(3), |n| n.parse::<usize>())?;
+    let runs = take_option(&mut args, "--runs")?.map_or(Ok(20), |n| n.parse::<usize>())?;
+
+    // --format text|json|tsv|markdown chooses how answers are printed, for scripts and the README.
+    // --json is short for --format json.
+    let format = take_option(&mut args, "--format")?;
+    let json = take_flag(&mut args, "--json");
+    let format = match (format, json) {
+        (Some(value), json) => {
+            let format = report::Format::try_from(value.as_str())?;
+            if json && format != report::Format::Json {
+                return Err(format!("--json conflicts with --format {}.", format).into());
+            }
+            format
+        }
+        (None, true) => report::Format::Json,
+        (None, false) => report::Format::Text,
+    };
+
+    // Option for watch mode.
+    let poll = take_flag(&mut args, "--poll");
+
+    if let Some(dir) = take_option(&mut args, "--input-dir")? {
+        input::set_input_dir(dir.into());
+    }
+
+    let mut args = args.into_iter();
+
+    let day = args.next().expect(
+        "Please specify the day with two digits, 'list', 'all', 'verify', 'bench', 'watch' or 'query'.",
+    );
+
+    let one_day = !["list", "verify", "all", "bench", "watch"].contains(&day.as_str());
+    if !one_day && !settings.is_empty() {
+        return Err("--set only applies when solving or querying one day.".into());
+    }
+
+    if day == "list" {
+        return registry::list(DAYS);
+    }
+
+    if day == "verify" {
+        let checks = answers::verify(DAYS)?;
+        answers::print_checks(&checks);
+        let failures = checks
+            .iter()
+            .flat_map(|c| c.verdicts.iter())
+            .filter(|v| matches!(v, answers::Verdict::Fail { .. }))
+            .count();
+        if failures > 0 {
+            return Err(format!("{} answers failed verification.", failures).into());
+        }
+        return Ok(());
+    }
+
+    if day == "all" {
+        let input_file = args.next().unwrap_or(String::from("input"));
+        let runs = runner::run_all(DAYS, &input_file);
+        report::print(&runs, format);
+        let failures = runs.iter().filter(|r| r.failed()).count();
+        if failures > 0 {
+            return Err(format!("{} of {} days failed.", failures, runs.len()).into());
+        }
+        return Ok(());
+    }
+
+    let benchmark = day == "bench";
+    let watching = day == "watch";
+    let querying = day == "query";
+    let day = match benchmark || watching || querying {
+        true => args
+            .next()
+            .expect("Please specify the day to benchmark, watch or query."),
+        false => day,
+    };
+
+    let day = registry::find(DAYS, &day).ok_or("Day given is not implemented.")?;
 
     let input_file = args
         .next()
-        .expect("Please specify the input file in the day directory.");
-
-    let inpuf_file_path = format!("{}/{}", day, input_file);
-    let input = std::fs::read_to_string(inpuf_file_path)?;
-
-    let mut puzzle_solver: Box<dyn Solve> = match day.as_str() {
-        "01" => Box::new(Day01::from(input)),
-        "02" => Box::new(Day02::from(input)),
-        "03" => Box::new(Day03::from(input)),
-        "04" => Box::new(Day04::from(input)),
-        "05" => Box::new(Day05::from(input)),
-        "06" => Box::new(Day06::from(input)),
-        "07" => Box::new(Day07::from(input)),
-        _ => return Err("Day given is not implemented.".into()),
+        .expect("Please specify the input file in the day directory, a path, or - for stdin.");
+
+    let parts = match part {
+        Some(part) => vec![part],
+        None => vec![Part::One, Part::Two],
     };
 
-    let solution = puzzle_solver.solve()?;
+    if watching {
+        return watch::watch(day, &input_file, &parts, poll);
+    }
+
+    if !benchmark && !querying && format != report::Format::Text {
+        let run = runner::run_day_with(day, &input_file, &parts, &settings);
+        report::print(std::slice::from_ref(&run), format);
+        if run.failed() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let source = input::Source::resolve(day, &input_file);
+    let input = source
+        .read()
+        .map_err(|e| format!("Cannot read {}: {}", source.name(), e))?;
+
+    if benchmark {
+        let json = match format {
+            report::Format::Text => false,
+            report::Format::Json => true,
+            _ => return Err(format!("Benchmarks print as text or json, not {}.", format).into()),
+        };
+        let results = bench::bench(day, &input_file, &input, warmup, runs)?;
+        match json {
+            true => println!("{}", results.to_json()),
+            false => results.print(),
+        }
+        return Ok(());
+    }
+
+    let mut puzzle_solver = match (day.build)(&input) {
+        Ok(solver) => solver,
+        Err(e) => {
+            eprintln!("{}", e.diagnostic(&source.name(), &input));
+            std::process::exit(1);
+        }
+    };
+
+    for setting in &settings {
+        puzzle_solver.configure(setting)?;
+    }
+
+    if querying {
+        println!("{}", puzzle_solver.query(&args.collect::<Vec<_>>())?);
+        return Ok(());
+    }
 
-    println!("{}", solution);
-    return Ok(());
+    // Each part is solved and printed on its own, so an error in one doesn't hide the other's answer.
+    let mut failed = false;
+    for part in parts {
+        match puzzle_solver.solve_part(part) {
+            Ok(answer) => println!("{}", PartAnswer(part, &answer)),
+            Err(e) => {
+                eprintln!("{}: error: {}", part, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
 }
@@ -0,0 +1,228 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// An error in the puzzle input, pointing at the text that could not be parsed.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub day: u8,
+    /// Line number in the input, starting at 1.
+    pub line: usize,
+    /// Column of the offending text in the line, starting at 1.
+    pub column: usize,
+    /// The offending text. This is empty when something is missing rather than wrong.
+    pub text: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "day {:02}, line {}, column {}: {}",
+            self.day, self.line, self.column, self.message
+        )
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl ParseError {
+    /// Renders the error like a compiler diagnostic, quoting the input line it points at.
+    pub fn diagnostic(&self, path: &str, input: &str) -> String {
+        let source_line = input.lines().nth(self.line - 1).unwrap_or("");
+        let gutter = " ".repeat(self.line.to_string().len());
+        let marker = "^".repeat(self.text.chars().count().max(1));
+        format!(
+            "error: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}",
+            self.message,
+            gutter,
+            path,
+            self.line,
+            self.column,
+            gutter,
+            self.line,
+            source_line,
+            gutter,
+            " ".repeat(self.column - 1),
+            marker
+        )
+    }
+}
+
+/// One line of puzzle input, remembering where it came from so errors can point into it.
+#[derive(Clone, Copy, Debug)]
+pub struct Line<'a> {
+    day: u8,
+    number: usize,
+    text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Creates an error for a piece of this line.
+    /// Tokens which are not slices of the line are reported at the start of the line.
+    pub fn error(&self, token: &str, message: impl Into<String>) -> ParseError {
+        let offset = (token.as_ptr() as usize).wrapping_sub(self.text.as_ptr() as usize);
+        let column = if offset <= self.text.len() {
+            self.text[..offset].chars().count() + 1
+        } else {
+            1
+        };
+        ParseError {
+            day: self.day,
+            line: self.number,
+            column,
+            text: String::from(token),
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error for something missing right after the given piece of this line.
+    pub fn missing_after(&self, token: &'a str, message: impl Into<String>) -> ParseError {
+        self.error(&token[token.len()..], message)
+    }
+
+    pub fn parse<T: FromStr>(&self, token: &'a str) -> Result<T, ParseError>
+    where
+        T::Err: fmt::Display,
+    {
+        token
+            .parse::<T>()
+            .map_err(|e| self.error(token, format!("invalid number '{}': {}", token, e)))
+    }
+
+    /// Parses every whitespace separated token in the given piece of this line.
+    pub fn parse_all<T: FromStr>(&self, tokens: &'a str) -> Result<Vec<T>, ParseError>
+    where
+        T::Err: fmt::Display,
+    {
+        tokens.split_whitespace().map(|t| self.parse(t)).collect()
+    }
+
+    pub fn split_once(
+        &self,
+        token: &'a str,
+        delimiter: &str,
+    ) -> Result<(&'a str, &'a str), ParseError> {
+        token
+            .split_once(delimiter)
+            .ok_or_else(|| self.missing_after(token, format!("expected '{}'", delimiter)))
+    }
+
+    pub fn strip_prefix(&self, token: &'a str, prefix: &str) -> Result<&'a str, ParseError> {
+        token.strip_prefix(prefix).ok_or_else(|| {
+            let found = &token[..token.len().min(prefix.len())];
+            self.error(found, format!("expected '{}'", prefix))
+        })
+    }
+}
+
+/// Iterates over the lines of puzzle input, numbering them for error messages.
+pub struct Lines<'a> {
+    day: u8,
+    number: usize,
+    lines: std::str::Lines<'a>,
+}
+
+impl<'a> Lines<'a> {
+    pub fn new(day: u8, input: &'a str) -> Self {
+        Lines {
+            day,
+            number: 0,
+            lines: input.lines(),
+        }
+    }
+
+    /// Returns the next line, or an error naming what was expected if the input has ended.
+    pub fn expect(&mut self, expected: &str) -> Result<Line<'a>, ParseError> {
+        self.next().ok_or_else(|| ParseError {
+            day: self.day,
+            line: self.number + 1,
+            column: 1,
+            text: String::new(),
+            message: format!("expected {}, found the end of the input", expected),
+        })
+    }
+
+    /// Returns the next line, which must be exactly the given text.
+    pub fn expect_exact(&mut self, expected: &str) -> Result<Line<'a>, ParseError> {
+        let description = match expected {
+            "" => String::from("an empty line"),
+            _ => format!("'{}'", expected),
+        };
+        let line = self.expect(&description)?;
+        if line.text() != expected {
+            return Err(line.error(line.text(), format!("expected {}", description)));
+        }
+        Ok(line)
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.lines.next()?;
+        self.number += 1;
+        Some(Line {
+            day: self.day,
+            number: self.number,
+            text,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_column() {
+        let line = Lines::new(2, "Game 3: 1 purple").next().unwrap();
+        let error = line.error(&line.text()[10..], "invalid color 'purple'");
+        assert_eq!(error.line, 1);
+        assert_eq!(error.column, 11);
+        assert_eq!(error.text, "purple");
+    }
+
+    #[test]
+    fn parse_number() {
+        let line = Lines::new(4, "Card 1: 41 4x 83").next().unwrap();
+        assert_eq!(line.parse::<i64>(&line.text()[8..10]), Ok(41));
+        let error = line.parse_all::<i64>(&line.text()[7..]).unwrap_err();
+        assert_eq!(error.column, 12);
+        assert_eq!(error.text, "4x");
+    }
+
+    #[test]
+    fn missing_delimiter() {
+        let line = Lines::new(2, "Game 1 3 blue").next().unwrap();
+        let error = line.split_once(line.text(), ":").unwrap_err();
+        assert_eq!(error.column, 14);
+        assert_eq!(error.message, "expected ':'");
+    }
+
+    #[test]
+    fn end_of_input() {
+        let mut lines = Lines::new(6, "Time: 7 15 30");
+        lines.expect("times").unwrap();
+        let error = lines.expect("distances").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.text, "");
+    }
+
+    #[test]
+    fn diagnostic() {
+        let input = "Game 1: 3 blue\nGame 2: 1 purple\n";
+        let line = Lines::new(2, input).nth(1).unwrap();
+        let error = line.error(&line.text()[10..], "invalid color 'purple'");
+        assert_eq!(
+            error.diagnostic("02/input", input),
+            "error: invalid color 'purple'\n --> 02/input:2:11\n  |\n2 | Game 2: 1 purple\n  |           ^^^^^^"
+        );
+    }
+}
@@ -1,19 +1,225 @@
 use std::error;
 use std::fmt;
 
+/// The answer to one part of a puzzle.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Answer {
+    Integer(i64),
+    Unsigned(u64),
+    Wide(i128),
+    /// Some puzzles are answered by reading letters drawn with characters.
+    Text(String),
+    /// What a part returns until it has been solved. Every day is solved for now.
+    #[allow(dead_code)]
+    Unsolved,
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self {
+        Answer::Integer(n)
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(n: u64) -> Self {
+        Answer::Unsigned(n)
+    }
+}
+
+impl From<i128> for Answer {
+    fn from(n: i128) -> Self {
+        Answer::Wide(n)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(text: String) -> Self {
+        Answer::Text(text)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(text: &str) -> Self {
+        Answer::Text(String::from(text))
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Integer(n) => write!(f, "{}", n),
+            Answer::Unsigned(n) => write!(f, "{}", n),
+            Answer::Wide(n) => write!(f, "{}", n),
+            Answer::Text(text) => write!(f, "{}", text),
+            Answer::Unsolved => write!(f, "(unsolved)"),
+        }
+    }
+}
+
+/// Both answers together, which only tests compare now that each part is printed on its own.
+#[cfg(test)]
 #[derive(Debug, PartialEq)]
-pub enum Solution {
-    Integer(i64, i64),
+pub struct Solution {
+    pub part1: Answer,
+    pub part2: Answer,
+}
+
+#[cfg(test)]
+impl Solution {
+    pub fn new(part1: Answer, part2: Answer) -> Self {
+        Solution { part1, part2 }
+    }
+
+    pub fn integer(part1: i64, part2: i64) -> Self {
+        Solution::new(Answer::Integer(part1), Answer::Integer(part2))
+    }
+
+    /// A solution where only the first part has been solved so far.
+    pub fn part1_only(part1: Answer) -> Self {
+        Solution::new(part1, Answer::Unsolved)
+    }
 }
 
+/// Writes "label: answer", moving multi-line text answers onto their own lines so letter art stays aligned.
+fn write_answer(f: &mut fmt::Formatter, label: &dyn fmt::Display, answer: &Answer) -> fmt::Result {
+    match answer {
+        Answer::Text(text) if text.contains('\n') => write!(f, "{}:\n{}", label, text.trim_end()),
+        _ => write!(f, "{}: {}", label, answer),
+    }
+}
+
+#[cfg(test)]
 impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_answer(f, &Part::One, &self.part1)?;
+        writeln!(f)?;
+        write_answer(f, &Part::Two, &self.part2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl TryFrom<&str> for Part {
+    type Error = String;
+
+    fn try_from(part: &str) -> Result<Self, Self::Error> {
+        match part {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            _ => Err(format!("Invalid part '{}', expected 1 or 2.", part)),
+        }
+    }
+}
+
+impl fmt::Display for Part {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Solution::Integer(p1, p2) => write!(f, "part1: {}\npart2: {}", p1, p2),
+            Part::One => write!(f, "part1"),
+            Part::Two => write!(f, "part2"),
+        }
+    }
+}
+
+/// A `--set key=value` option from the command line, applied with `Solve::configure`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Setting {
+    pub key: String,
+    pub value: String,
+}
+
+impl TryFrom<&str> for Setting {
+    type Error = String;
+
+    fn try_from(setting: &str) -> Result<Self, Self::Error> {
+        match setting.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok(Setting {
+                key: String::from(key),
+                value: String::from(value),
+            }),
+            _ => Err(format!(
+                "Invalid setting '{}', expected key=value.",
+                setting
+            )),
         }
     }
 }
 
+/// Each part is solved independently from the parsed input.
+/// Solvers must not carry state from one part to the other, so the parts can be called alone, in any order, or more than once.
 pub trait Solve {
-    fn solve(&mut self) -> Result<Solution, Box<dyn error::Error>>;
+    fn solve_p1(&self) -> Result<Answer, Box<dyn error::Error>>;
+
+    fn solve_p2(&self) -> Result<Answer, Box<dyn error::Error>>;
+
+    fn solve_part(&self, part: Part) -> Result<Answer, Box<dyn error::Error>> {
+        match part {
+            Part::One => self.solve_p1(),
+            Part::Two => self.solve_p2(),
+        }
+    }
+
+    #[cfg(test)]
+    fn solve(&self) -> Result<Solution, Box<dyn error::Error>> {
+        Ok(Solution::new(self.solve_p1()?, self.solve_p2()?))
+    }
+
+    /// Changes how the puzzle is solved, e.g. `--set inequality=non-strict`.
+    /// Days without settings reject every key.
+    fn configure(&mut self, setting: &Setting) -> Result<(), Box<dyn error::Error>> {
+        Err(format!("Unknown setting '{}'.", setting.key).into())
+    }
+
+    /// Answers a question about the parsed input, asked with the words after the input on the command line,
+    /// e.g. `query 05 input lowest 2`. Days without anything to explore have no queries.
+    fn query(&self, args: &[String]) -> Result<String, Box<dyn error::Error>> {
+        _ = args;
+        Err("This day has no queries.".into())
+    }
+}
+
+/// Prints a single part the same way Solution prints both.
+pub struct PartAnswer<'a>(pub Part, pub &'a Answer);
+
+impl fmt::Display for PartAnswer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_answer(f, &self.0, self.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_solution() {
+        assert_eq!(
+            Solution::integer(142, -3).to_string(),
+            "part1: 142\npart2: -3"
+        );
+        assert_eq!(
+            Solution::new(Answer::Unsigned(u64::MAX), Answer::Wide(i128::MIN)).to_string(),
+            format!("part1: {}\npart2: {}", u64::MAX, i128::MIN)
+        );
+        assert_eq!(
+            Solution::part1_only(Answer::from("abc")).to_string(),
+            "part1: abc\npart2: (unsolved)"
+        );
+    }
+
+    #[test]
+    fn display_letter_art() {
+        let art = Answer::from("#..#\n####\n#..#\n");
+        assert_eq!(
+            Solution::new(art.clone(), Answer::Integer(1)).to_string(),
+            "part1:\n#..#\n####\n#..#\npart2: 1"
+        );
+        assert_eq!(
+            PartAnswer(Part::Two, &art).to_string(),
+            "part2:\n#..#\n####\n#..#"
+        );
+    }
 }
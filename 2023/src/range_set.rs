@@ -0,0 +1,394 @@
+use std::fmt::{self, Debug};
+use std::ops::Range;
+
+/// The integer types a RangeSet can hold.
+pub trait Integer: Copy + Ord + Debug {
+    const MIN: Self;
+    const MAX: Self;
+
+    #[allow(dead_code)]
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    /// The number of values in start..end, which may not fit in the type itself.
+    #[allow(dead_code)]
+    fn distance(start: Self, end: Self) -> u128;
+}
+
+macro_rules! integer {
+    ($($t:ty),*) => {
+        $(impl Integer for $t {
+            const MIN: Self = <$t>::MIN;
+            const MAX: Self = <$t>::MAX;
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+
+            fn distance(start: Self, end: Self) -> u128 {
+                end.abs_diff(start) as u128
+            }
+        })*
+    };
+}
+
+integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// A set of integers stored as sorted, disjoint ranges.
+/// Ranges which overlap or touch are coalesced, so each set has exactly one representation.
+///
+/// Ranges are half open, so the largest value of the type can never be a member,
+/// and the complement is taken within `T::MIN..T::MAX`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RangeSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Integer> RangeSet<T> {
+    pub fn new() -> Self {
+        RangeSet { ranges: vec![] }
+    }
+
+    /// Sorts the ranges and merges any which overlap or touch, dropping empty ones.
+    fn coalesce(mut ranges: Vec<Range<T>>) -> Self {
+        ranges.retain(|r| r.start < r.end);
+        ranges.sort_by_key(|r| r.start);
+        let mut coalesced: Vec<Range<T>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match coalesced.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => coalesced.push(range),
+            }
+        }
+        RangeSet { ranges: coalesced }
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The number of values in the set.
+    #[allow(dead_code)]
+    pub fn len(&self) -> u128 {
+        self.ranges
+            .iter()
+            .map(|r| T::distance(r.start, r.end))
+            .sum()
+    }
+
+    #[allow(dead_code)]
+    pub fn contains(&self, value: T) -> bool {
+        // The last range starting at or before the value is the only one which can hold it.
+        let i = self.ranges.partition_point(|r| r.start <= value);
+        i > 0 && value < self.ranges[i - 1].end
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    #[allow(dead_code)]
+    pub fn insert(&mut self, range: Range<T>) {
+        let mut ranges = std::mem::take(&mut self.ranges);
+        ranges.push(range);
+        *self = RangeSet::coalesce(ranges);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        RangeSet::coalesce(self.ranges.iter().chain(&other.ranges).cloned().collect())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+            // Whichever range ends first can't overlap anything further along the other set.
+            match a.end <= b.end {
+                true => i += 1,
+                false => j += 1,
+            }
+        }
+        // Overlaps of disjoint, sorted ranges are themselves disjoint and sorted, and can't touch.
+        RangeSet { ranges }
+    }
+
+    /// Every value in `T::MIN..T::MAX` which is not in the set.
+    pub fn complement(&self) -> Self {
+        let mut ranges = vec![];
+        let mut start = T::MIN;
+        for range in &self.ranges {
+            if start < range.start {
+                ranges.push(start..range.start);
+            }
+            start = range.end;
+        }
+        if start < T::MAX {
+            ranges.push(start..T::MAX);
+        }
+        RangeSet { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.intersection(&other.complement())
+    }
+
+    /// Adds `by` to every value, or returns None if any value would overflow.
+    #[allow(dead_code)]
+    pub fn checked_offset(&self, by: T) -> Option<Self> {
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|r| Some(r.start.checked_add(by)?..r.end.checked_add(by)?))
+            .collect::<Option<_>>()?;
+        Some(RangeSet { ranges })
+    }
+
+    /// Adds `by` to every value, panicking on overflow like integer arithmetic does.
+    #[allow(dead_code)]
+    pub fn offset(&self, by: T) -> Self {
+        self.checked_offset(by)
+            .unwrap_or_else(|| panic!("offsetting {:?} by {:?} overflows", self.ranges, by))
+    }
+}
+
+impl<T: Integer> From<Range<T>> for RangeSet<T> {
+    fn from(range: Range<T>) -> Self {
+        RangeSet::coalesce(vec![range])
+    }
+}
+
+/// Writes the ranges like `{0..3, 5..10}`.
+impl<T: Integer + fmt::Display> fmt::Display for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges: Vec<String> = self
+            .ranges
+            .iter()
+            .map(|r| format!("{}..{}", r.start, r.end))
+            .collect();
+        write!(f, "{{{}}}", ranges.join(", "))
+    }
+}
+
+impl<T: Integer> FromIterator<Range<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        RangeSet::coalesce(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[Range<i64>]) -> RangeSet<i64> {
+        ranges.iter().cloned().collect()
+    }
+
+    #[test]
+    fn coalesce_ranges() {
+        assert_eq!(set(&[5..7, 0..2, 2..3, 6..10]).ranges(), &[0..3, 5..10]);
+        assert_eq!(set(&[{ 3..3 }]), RangeSet::new());
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[0..10, 20..30]);
+        let b = set(&[{ 5..25 }]);
+        assert_eq!(a.union(&b), set(&[{ 0..30 }]));
+        assert_eq!(a.intersection(&b), set(&[5..10, 20..25]));
+        assert_eq!(a.difference(&b), set(&[0..5, 25..30]));
+        assert_eq!(a.complement(), set(&[i64::MIN..0, 10..20, 30..i64::MAX]));
+    }
+
+    /// The cases the old day 5 range_remainder helper enumerated by hand.
+    #[test]
+    fn range_differences() {
+        let difference = |a: Range<i64>, b: Range<i64>| set(&[a]).difference(&set(&[b]));
+        assert_eq!(difference(10..25, 10..20), set(&[{ 20..25 }]));
+        assert_eq!(difference(5..10, 0..5), set(&[{ 5..10 }]));
+        assert_eq!(difference(5..10, 0..6), set(&[{ 6..10 }]));
+        assert_eq!(difference(5..10, 6..8), set(&[5..6, 8..10]));
+        assert_eq!(difference(5..10, 7..20), set(&[{ 5..7 }]));
+        assert_eq!(difference(5..10, 10..20), set(&[{ 5..10 }]));
+        assert_eq!(difference(10..15, 5..20), RangeSet::new());
+    }
+
+    #[test]
+    fn queries() {
+        let a = set(&[0..10, 20..30]);
+        assert!(a.contains(0) && a.contains(29));
+        assert!(!a.contains(10) && !a.contains(-1) && !a.contains(30));
+        assert_eq!(a.len(), 20);
+        assert_eq!(a.min(), Some(0));
+        assert_eq!(RangeSet::from(i8::MIN..i8::MAX).len(), 255);
+        assert_eq!(RangeSet::from(i128::MIN..i128::MAX).len(), u128::MAX);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(set(&[0..3, 5..10]).to_string(), "{0..3, 5..10}");
+        assert_eq!(RangeSet::<u8>::new().to_string(), "{}");
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(set(&[0..5, 7..8]).offset(10), set(&[10..15, 17..18]));
+        assert_eq!(set(&[{ 10..15 }]).offset(-10), set(&[{ 0..5 }]));
+        assert_eq!(RangeSet::from(250u8..255).checked_offset(1), None);
+    }
+
+    /// A small xorshift generator, so the property tests are reproducible without extra crates.
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    /// Every value of a byte-sized type, as the index of a bitset.
+    trait Byte: Integer {
+        fn index(self) -> usize;
+        fn from_index(index: usize) -> Self;
+    }
+
+    impl Byte for u8 {
+        fn index(self) -> usize {
+            self as usize
+        }
+
+        fn from_index(index: usize) -> Self {
+            index as u8
+        }
+    }
+
+    impl Byte for i8 {
+        fn index(self) -> usize {
+            (self as i16 + 128) as usize
+        }
+
+        fn from_index(index: usize) -> Self {
+            (index as i16 - 128) as i8
+        }
+    }
+
+    type Bits = [bool; 256];
+
+    fn random_ranges<T: Byte>(random: &mut Random) -> Vec<Range<T>> {
+        (0..random.below(5))
+            .map(|_| {
+                let start = random.below(255) as usize;
+                let end = (start + random.below(40) as usize).min(255);
+                T::from_index(start)..T::from_index(end)
+            })
+            .collect()
+    }
+
+    fn bits<T: Byte>(ranges: &[Range<T>]) -> Bits {
+        let mut bits = [false; 256];
+        for range in ranges {
+            for bit in &mut bits[range.start.index()..range.end.index()] {
+                *bit = true;
+            }
+        }
+        bits
+    }
+
+    fn bits_of<T: Byte>(set: &RangeSet<T>) -> Bits {
+        bits(set.ranges())
+    }
+
+    fn combine(a: &Bits, b: &Bits, f: impl Fn(bool, bool) -> bool) -> Bits {
+        std::array::from_fn(|i| f(a[i], b[i]))
+    }
+
+    fn check_properties<T: Byte>(seed: u64) {
+        let mut random = Random(seed);
+        for _ in 0..500 {
+            let a_ranges = random_ranges::<T>(&mut random);
+            let b_ranges = random_ranges::<T>(&mut random);
+            let (a, b): (RangeSet<T>, RangeSet<T>) = (
+                a_ranges.iter().cloned().collect(),
+                b_ranges.iter().cloned().collect(),
+            );
+            let (a_bits, b_bits) = (bits(&a_ranges), bits(&b_ranges));
+
+            // The set holds exactly the values of its ranges, in the one coalesced form.
+            assert_eq!(bits_of(&a), a_bits, "{:?}", a_ranges);
+            assert!(a.ranges().windows(2).all(|w| w[0].end < w[1].start));
+            assert!(a.ranges().iter().all(|r| r.start < r.end));
+            assert_eq!(a.len(), a_bits.iter().filter(|&&b| b).count() as u128);
+            for (index, &bit) in a_bits.iter().enumerate() {
+                assert_eq!(a.contains(T::from_index(index)), bit);
+            }
+
+            assert_eq!(
+                bits_of(&a.union(&b)),
+                combine(&a_bits, &b_bits, |x, y| x || y)
+            );
+            assert_eq!(
+                bits_of(&a.intersection(&b)),
+                combine(&a_bits, &b_bits, |x, y| x && y)
+            );
+            assert_eq!(
+                bits_of(&a.difference(&b)),
+                combine(&a_bits, &b_bits, |x, y| x && !y)
+            );
+            // The largest value is never a member, so it's not in the complement either.
+            let mut complement: Bits = std::array::from_fn(|i| !a_bits[i]);
+            complement[255] = false;
+            assert_eq!(bits_of(&a.complement()), complement);
+        }
+    }
+
+    #[test]
+    fn unsigned_properties() {
+        check_properties::<u8>(0x2023_1205);
+    }
+
+    #[test]
+    fn signed_properties() {
+        check_properties::<i8>(0x5eed_5eed);
+    }
+
+    #[test]
+    fn offset_properties() {
+        let mut random = Random(42);
+        for _ in 0..500 {
+            let ranges = random_ranges::<i8>(&mut random);
+            let set: RangeSet<i8> = ranges.iter().cloned().collect();
+            let by = random.below(64) as i8 - 32;
+            let bits = bits(&ranges);
+            match set.checked_offset(by) {
+                Some(shifted) => {
+                    for (i, &bit) in bits.iter().enumerate() {
+                        let moved = i as isize + by as isize;
+                        if bit {
+                            assert!(shifted.contains(i8::from_index(moved as usize)));
+                        }
+                    }
+                    assert_eq!(shifted.len(), set.len());
+                }
+                // Only a set reaching within `by` of an end of the type can overflow.
+                None => assert!(set.ranges().iter().any(|r| {
+                    r.start.checked_add(by).is_none() || r.end.checked_add(by).is_none()
+                })),
+            }
+        }
+    }
+}
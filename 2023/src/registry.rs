@@ -0,0 +1,120 @@
+use crate::answers::{self, Answers};
+use crate::input;
+use crate::parse::ParseError;
+use crate::puzzle::Solve;
+use std::path::PathBuf;
+
+/// Everything the runner needs to know about one day's puzzle.
+/// Each dayNN module provides one of these as `DAY`.
+pub struct Day {
+    pub number: u8,
+    pub title: &'static str,
+    pub build: fn(&str) -> Result<Box<dyn Solve>, ParseError>,
+}
+
+impl Day {
+    /// The name of the directory holding this day's inputs, e.g. "05".
+    pub fn directory(&self) -> String {
+        format!("{:02}", self.number)
+    }
+
+    /// The path of an input file in this day's directory.
+    pub fn input_path(&self, file: &str) -> PathBuf {
+        input::input_dir().join(self.directory()).join(file)
+    }
+
+    /// Lists the input files present in this day's directory, leaving out the answers sidecar file.
+    pub fn input_files(&self) -> Vec<String> {
+        let mut files: Vec<String> = std::fs::read_dir(input::input_dir().join(self.directory()))
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter(|e| e.path().is_file())
+                    .filter_map(|e| e.file_name().into_string().ok())
+                    .filter(|name| name != answers::FILE_NAME)
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+}
+
+/// Collects the `DAY` registrations of the day modules into `DAYS`.
+/// The modules are declared in main.rs as usual, so rustfmt and other tools still find them.
+macro_rules! days {
+    ($($module:ident),* $(,)?) => {
+        const DAYS: &[&registry::Day] = &[$(&$module::DAY),*];
+    };
+}
+
+pub(crate) use days;
+
+/// Finds a day by its number, written with or without a leading zero.
+pub fn find<'a>(days: &[&'a Day], day: &str) -> Option<&'a Day> {
+    let number = day.parse::<u8>().ok()?;
+    days.iter().copied().find(|d| d.number == number)
+}
+
+/// Prints each registered day, the inputs in its directory, and which answers are recorded for them.
+pub fn list(days: &[&Day]) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{:<4} {:<32} {:<8} Answers", "Day", "Title", "Input");
+    for day in days {
+        let answers = Answers::load(day)?;
+        let mut files = day.input_files();
+        for file in answers.files() {
+            if !files.contains(file) {
+                files.push(file.clone());
+            }
+        }
+
+        if files.is_empty() {
+            println!("{:<4} {:<32} {:<8} -", day.directory(), day.title, "-");
+        }
+
+        for (i, file) in files.iter().enumerate() {
+            let (number, title) = match i {
+                0 => (day.directory(), day.title),
+                _ => (String::new(), ""),
+            };
+            let exists = day.input_path(file).is_file();
+            let answers = match answers.get(file) {
+                Some(e) => match (&e.part1, &e.part2) {
+                    (Some(_), Some(_)) => "part1, part2",
+                    (Some(_), None) => "part1",
+                    (None, Some(_)) => "part2",
+                    (None, None) => "-",
+                },
+                None => "-",
+            };
+            let missing = if exists { "" } else { " (file missing)" };
+            println!(
+                "{:<4} {:<32} {:<8} {}{}",
+                number, title, file, answers, missing
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_are_unique_and_ordered() {
+        let numbers: Vec<u8> = crate::DAYS.iter().map(|d| d.number).collect();
+        assert!(numbers.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn find_day() {
+        assert_eq!(
+            find(crate::DAYS, "05").map(|d| d.title),
+            Some("If You Give A Seed A Fertilizer")
+        );
+        assert_eq!(find(crate::DAYS, "7").map(|d| d.number), Some(7));
+        assert!(find(crate::DAYS, "25").is_none());
+        assert!(find(crate::DAYS, "list").is_none());
+    }
+}
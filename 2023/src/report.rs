@@ -0,0 +1,291 @@
+use crate::puzzle::{Answer, Part};
+use crate::runner::{self, PartRun, Run};
+use std::fmt;
+use std::time::Duration;
+
+/// How the runner prints its results.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// The answers as "part1: X" lines for one day, a table for several.
+    Text,
+    /// One object per run, with times in nanoseconds.
+    Json,
+    /// A header row and one row per run, with times in nanoseconds.
+    Tsv,
+    /// A table for pasting into the README.
+    Markdown,
+}
+
+impl TryFrom<&str> for Format {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "tsv" => Ok(Format::Tsv),
+            "markdown" | "md" => Ok(Format::Markdown),
+            _ => Err(format!(
+                "Unknown format '{}', expected text, json, tsv or markdown.",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Text => write!(f, "text"),
+            Format::Json => write!(f, "json"),
+            Format::Tsv => write!(f, "tsv"),
+            Format::Markdown => write!(f, "markdown"),
+        }
+    }
+}
+
+/// Quotes a string for JSON output.
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn json_or_null(value: Option<String>) -> String {
+    value.unwrap_or(String::from("null"))
+}
+
+/// Answers are always strings, as some don't fit in a JSON number and some are letters. An unsolved part is null.
+fn json_part(part: &PartRun) -> String {
+    let (answer, error) = match &part.answer {
+        Ok(Answer::Unsolved) => (None, None),
+        Ok(answer) => (Some(json_string(&answer.to_string())), None),
+        Err(e) => (None, Some(json_string(e))),
+    };
+    format!(
+        "{{\"part\":{},\"answer\":{},\"time_ns\":{},\"error\":{}}}",
+        match part.part {
+            Part::One => 1,
+            Part::Two => 2,
+        },
+        json_or_null(answer),
+        part.time.as_nanos(),
+        json_or_null(error)
+    )
+}
+
+fn json_run(run: &Run) -> String {
+    let parts: Vec<String> = run.parts.iter().map(json_part).collect();
+    format!(
+        "{{\"day\":{},\"input\":{},\"parse_ns\":{},\"error\":{},\"parts\":[{}]}}",
+        run.day,
+        json_string(&run.input),
+        json_or_null(run.parse.as_ref().ok().map(|t| t.as_nanos().to_string())),
+        json_or_null(run.parse.as_ref().err().map(|e| json_string(e))),
+        parts.join(",")
+    )
+}
+
+/// Renders the runs as a JSON array.
+pub fn json(runs: &[Run]) -> String {
+    let runs: Vec<String> = runs.iter().map(json_run).collect();
+    format!("[{}]", runs.join(","))
+}
+
+/// Every error of a run on one line: the parse error, or the errors of each part.
+fn errors(run: &Run) -> String {
+    match &run.parse {
+        Err(e) => e.clone(),
+        Ok(_) => run
+            .parts
+            .iter()
+            .filter_map(|p| {
+                p.answer
+                    .as_ref()
+                    .err()
+                    .map(|e| format!("{}: {}", p.part, e))
+            })
+            .collect::<Vec<_>>()
+            .join("; "),
+    }
+}
+
+fn tsv_cell(cell: &str) -> String {
+    cell.replace(['\t', '\n'], " ")
+}
+
+fn tsv_answer(answer: Option<&Result<Answer, String>>) -> String {
+    match answer {
+        Some(Ok(Answer::Unsolved)) | Some(Err(_)) | None => String::new(),
+        Some(Ok(answer)) => tsv_cell(&answer.to_string()),
+    }
+}
+
+fn nanos(time: Duration) -> String {
+    time.as_nanos().to_string()
+}
+
+/// Renders the runs as tab separated values. Missing answers and times are empty cells.
+pub fn tsv(runs: &[Run]) -> String {
+    let mut lines = vec![String::from(
+        "day\tinput\tpart1\tpart2\tparse_ns\tsolve_ns\terror",
+    )];
+    for run in runs {
+        let (parse, solve) = match &run.parse {
+            Ok(parse_time) => (nanos(*parse_time), nanos(run.solve_time())),
+            Err(_) => (String::new(), String::new()),
+        };
+        let cells = [
+            run.day.to_string(),
+            tsv_cell(&run.input),
+            tsv_answer(run.answer(Part::One)),
+            tsv_answer(run.answer(Part::Two)),
+            parse,
+            solve,
+            tsv_cell(&errors(run)),
+        ];
+        lines.push(cells.join("\t"));
+    }
+    lines.join("\n")
+}
+
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Renders the runs as a markdown table.
+pub fn markdown(runs: &[Run]) -> String {
+    let mut lines = vec![
+        String::from("| Day | Input | Part 1 | Part 2 | Parse | Solve |"),
+        String::from("| --: | ----- | ------ | ------ | ----: | ----: |"),
+    ];
+    for run in runs {
+        let cells = match &run.parse {
+            Ok(parse_time) => [
+                runner::answer_cell(run.answer(Part::One)),
+                runner::answer_cell(run.answer(Part::Two)),
+                format!("{:.2?}", parse_time),
+                format!("{:.2?}", run.solve_time()),
+            ],
+            Err(e) => [
+                format!("FAILED: {}", e),
+                String::new(),
+                String::from("-"),
+                String::from("-"),
+            ],
+        };
+        let cells: Vec<String> = [format!("{:02}", run.day), run.input.clone()]
+            .iter()
+            .chain(&cells)
+            .map(|c| markdown_cell(c))
+            .collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+    }
+    lines.join("\n")
+}
+
+/// Prints the runs in the given format.
+pub fn print(runs: &[Run], format: Format) {
+    match format {
+        Format::Text => runner::print_table(runs),
+        Format::Json => println!("{}", json(runs)),
+        Format::Tsv => println!("{}", tsv(runs)),
+        Format::Markdown => println!("{}", markdown(runs)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs() -> Vec<Run> {
+        vec![
+            Run {
+                day: 5,
+                input: String::from("01"),
+                parse: Ok(Duration::from_nanos(1500)),
+                parts: vec![
+                    PartRun {
+                        part: Part::One,
+                        answer: Ok(Answer::Integer(35)),
+                        time: Duration::from_nanos(200),
+                    },
+                    PartRun {
+                        part: Part::Two,
+                        answer: Err(String::from("panicked: oops")),
+                        time: Duration::from_nanos(300),
+                    },
+                ],
+            },
+            Run {
+                day: 7,
+                input: String::from("input"),
+                parse: Err(String::from("day 07, line 1, column 1: bad | card")),
+                parts: vec![],
+            },
+        ]
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(Format::try_from("md"), Ok(Format::Markdown));
+        assert_eq!(Format::try_from("json"), Ok(Format::Json));
+        assert!(Format::try_from("xml").is_err());
+    }
+
+    #[test]
+    fn quote_json() {
+        assert_eq!(json_string("a\"b\\c\nd"), "\"a\\\"b\\\\c\\nd\"");
+    }
+
+    #[test]
+    fn runs_as_json() {
+        assert_eq!(
+            json(&runs()),
+            concat!(
+                "[{\"day\":5,\"input\":\"01\",\"parse_ns\":1500,\"error\":null,\"parts\":[",
+                "{\"part\":1,\"answer\":\"35\",\"time_ns\":200,\"error\":null},",
+                "{\"part\":2,\"answer\":null,\"time_ns\":300,\"error\":\"panicked: oops\"}]},",
+                "{\"day\":7,\"input\":\"input\",\"parse_ns\":null,",
+                "\"error\":\"day 07, line 1, column 1: bad | card\",\"parts\":[]}]"
+            )
+        );
+    }
+
+    #[test]
+    fn runs_as_tsv() {
+        assert_eq!(
+            tsv(&runs()),
+            concat!(
+                "day\tinput\tpart1\tpart2\tparse_ns\tsolve_ns\terror\n",
+                "5\t01\t35\t\t1500\t500\tpart2: panicked: oops\n",
+                "7\tinput\t\t\t\t\tday 07, line 1, column 1: bad | card"
+            )
+        );
+    }
+
+    #[test]
+    fn runs_as_markdown() {
+        let table = markdown(&runs());
+        let rows: Vec<&str> = table.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[2],
+            "| 05 | 01 | 35 | FAILED: panicked: oops | 1.50µs | 500.00ns |"
+        );
+        assert_eq!(
+            rows[3],
+            "| 07 | input | FAILED: day 07, line 1, column 1: bad \\| card |  | - | - |"
+        );
+    }
+}
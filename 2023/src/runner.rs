@@ -0,0 +1,267 @@
+use crate::input::Source;
+use crate::puzzle::{Answer, Part, Setting};
+use crate::registry::Day;
+use std::panic;
+use std::time::{Duration, Instant};
+
+/// The outcome of solving one part.
+pub struct PartRun {
+    pub part: Part,
+    pub answer: Result<Answer, String>,
+    pub time: Duration,
+}
+
+/// The outcome of running one day against one input file.
+pub struct Run {
+    pub day: u8,
+    /// The input argument the day was run against.
+    pub input: String,
+    /// How long parsing took, or why the input could not be read or parsed.
+    pub parse: Result<Duration, String>,
+    pub parts: Vec<PartRun>,
+}
+
+impl Run {
+    pub fn failed(&self) -> bool {
+        self.parse.is_err() || self.parts.iter().any(|p| p.answer.is_err())
+    }
+
+    pub fn solve_time(&self) -> Duration {
+        self.parts.iter().map(|p| p.time).sum()
+    }
+
+    pub fn answer(&self, part: Part) -> Option<&Result<Answer, String>> {
+        self.parts
+            .iter()
+            .find(|p| p.part == part)
+            .map(|p| &p.answer)
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        format!("panicked: {}", message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        format!("panicked: {}", message)
+    } else {
+        String::from("panicked")
+    }
+}
+
+/// Runs a closure, turning a panic into an error message so one broken day can't stop the others.
+fn catch<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).map_err(panic_message)
+}
+
+/// Reads, parses and solves the given parts of one day, timing each phase.
+/// Errors and panics are recorded in the returned Run rather than propagated.
+pub fn run_day(day: &Day, input: &str, parts: &[Part]) -> Run {
+    run_day_with(day, input, parts, &[])
+}
+
+/// Runs one day like run_day, applying settings to the solver before solving.
+/// A setting the day rejects is reported like an input which can't be parsed.
+pub fn run_day_with(day: &Day, input: &str, parts: &[Part], settings: &[Setting]) -> Run {
+    let mut run = Run {
+        day: day.number,
+        input: String::from(input),
+        parse: Err(String::new()),
+        parts: vec![],
+    };
+
+    let source = Source::resolve(day, input);
+    let text = match source.read() {
+        Ok(text) => text,
+        Err(e) => {
+            run.parse = Err(format!("cannot read {}: {}", source.name(), e));
+            return run;
+        }
+    };
+
+    let start = Instant::now();
+    let mut solver = match catch(|| (day.build)(&text)) {
+        Ok(Ok(solver)) => solver,
+        Ok(Err(e)) => {
+            run.parse = Err(e.to_string());
+            return run;
+        }
+        Err(e) => {
+            run.parse = Err(e);
+            return run;
+        }
+    };
+    run.parse = Ok(start.elapsed());
+
+    for setting in settings {
+        if let Err(e) = solver.configure(setting) {
+            run.parse = Err(e.to_string());
+            return run;
+        }
+    }
+
+    for &part in parts {
+        let start = Instant::now();
+        let answer = match catch(|| solver.solve_part(part)) {
+            Ok(Ok(answer)) => Ok(answer),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(e) => Err(e),
+        };
+        run.parts.push(PartRun {
+            part,
+            answer,
+            time: start.elapsed(),
+        });
+    }
+    run
+}
+
+/// Runs a closure without printing panic messages, for when panics are caught and reported some other way.
+pub fn quiet_panics<T>(f: impl FnOnce() -> T) -> T {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = f();
+    panic::set_hook(hook);
+    result
+}
+
+/// Runs every day against the same input file name, keeping panics quiet so only the summary is printed.
+pub fn run_all(days: &[&Day], input: &str) -> Vec<Run> {
+    quiet_panics(|| {
+        days.iter()
+            .map(|day| run_day(day, input, &[Part::One, Part::Two]))
+            .collect()
+    })
+}
+
+pub fn answer_cell(answer: Option<&Result<Answer, String>>) -> String {
+    match answer {
+        Some(Ok(answer)) => answer.to_string().replace('\n', " "),
+        Some(Err(e)) => format!("FAILED: {}", e),
+        None => String::from("-"),
+    }
+}
+
+/// Lays out rows of cells in left aligned columns under a header.
+pub fn format_columns(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: &[String]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        String::from(line.join("  ").trim_end())
+    };
+
+    let mut lines = vec![
+        format_row(&headers.iter().map(|h| String::from(*h)).collect::<Vec<_>>()),
+        format_row(&widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>()),
+    ];
+    lines.extend(rows.iter().map(|row| format_row(row)));
+    lines.join("\n")
+}
+
+/// Prints rows of cells in left aligned columns under a header.
+pub fn print_columns(headers: &[&str], rows: &[Vec<String>]) {
+    println!("{}", format_columns(headers, rows));
+}
+
+/// Prints the runs as a table of answers and timings, one row per day.
+pub fn print_table(runs: &[Run]) {
+    let rows: Vec<Vec<String>> = runs
+        .iter()
+        .map(|run| match &run.parse {
+            Ok(parse_time) => vec![
+                format!("{:02}", run.day),
+                answer_cell(run.answer(Part::One)),
+                answer_cell(run.answer(Part::Two)),
+                format!("{:.2?}", parse_time),
+                format!("{:.2?}", run.solve_time()),
+            ],
+            Err(e) => vec![
+                format!("{:02}", run.day),
+                format!("FAILED: {}", e),
+                String::new(),
+                String::from("-"),
+                String::from("-"),
+            ],
+        })
+        .collect();
+    print_columns(&["Day", "Part 1", "Part 2", "Parse", "Solve"], &rows);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::ParseError;
+    use crate::puzzle::Solve;
+    use crate::registry::Day;
+
+    struct Panics;
+
+    impl Solve for Panics {
+        fn solve_p1(&self) -> Result<Answer, Box<dyn std::error::Error>> {
+            Ok(Answer::Integer(1))
+        }
+
+        fn solve_p2(&self) -> Result<Answer, Box<dyn std::error::Error>> {
+            panic!("A third part attached to a gear!?");
+        }
+    }
+
+    const PANICS: Day = Day {
+        number: 1,
+        title: "Panics",
+        build: |_| Ok(Box::new(Panics)),
+    };
+
+    const BAD_INPUT: Day = Day {
+        number: 2,
+        title: "Bad input",
+        build: |input| {
+            Err(ParseError {
+                day: 2,
+                line: 1,
+                column: 1,
+                text: String::from(input),
+                message: String::from("always wrong"),
+            })
+        },
+    };
+
+    #[test]
+    fn panics_are_reported() {
+        let run = run_day(&PANICS, "01", &[Part::One, Part::Two]);
+        assert!(run.failed());
+        assert_eq!(run.answer(Part::One), Some(&Ok(Answer::Integer(1))));
+        assert_eq!(
+            run.answer(Part::Two),
+            Some(&Err(String::from(
+                "panicked: A third part attached to a gear!?"
+            )))
+        );
+    }
+
+    #[test]
+    fn parse_errors_are_reported() {
+        let run = run_day(&BAD_INPUT, "01", &[Part::One, Part::Two]);
+        assert!(run.failed());
+        assert_eq!(
+            run.parse,
+            Err(String::from("day 02, line 1, column 1: always wrong"))
+        );
+        assert!(run.parts.is_empty());
+    }
+
+    #[test]
+    fn missing_input_is_reported() {
+        let run = run_day(&PANICS, "no-such-file", &[Part::One]);
+        assert!(run.parse.unwrap_err().starts_with("cannot read"));
+    }
+}
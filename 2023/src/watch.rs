@@ -0,0 +1,315 @@
+use crate::answers::{Answers, Verdict};
+use crate::input::{self, Source};
+use crate::puzzle::{Part, PartAnswer};
+use crate::registry::Day;
+use crate::runner;
+use std::collections::BTreeMap;
+use std::error;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often the polling watcher compares the watched directories with its last look at them.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long changes have to settle before re-running, so an editor saving a file in several steps causes one run.
+const SETTLE: Duration = Duration::from_millis(100);
+
+/// Linux file notifications, called directly so no crate or external tool is needed.
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::ffi::{c_char, c_int, CString};
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::FromRawFd;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    const IN_NONBLOCK: c_int = 0o4000;
+    const IN_CLOEXEC: c_int = 0o2000000;
+
+    const IN_MODIFY: u32 = 0x2;
+    const IN_ATTRIB: u32 = 0x4;
+    const IN_CLOSE_WRITE: u32 = 0x8;
+    const IN_MOVED_FROM: u32 = 0x40;
+    const IN_MOVED_TO: u32 = 0x80;
+    const IN_CREATE: u32 = 0x100;
+    const IN_DELETE: u32 = 0x200;
+
+    extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+    }
+
+    /// An inotify instance watching whole directories, so files replaced by renaming are noticed too.
+    pub struct Inotify {
+        file: File,
+    }
+
+    impl Inotify {
+        pub fn new(dirs: &[&Path]) -> io::Result<Self> {
+            // SAFETY: inotify_init1 takes no pointers, and a non-negative result is a new descriptor we own.
+            let fd = unsafe { inotify_init1(IN_NONBLOCK | IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: fd was just opened and nothing else owns it, the File closes it when dropped.
+            let inotify = Inotify {
+                file: unsafe { File::from_raw_fd(fd) },
+            };
+
+            let mask = IN_MODIFY
+                | IN_ATTRIB
+                | IN_CLOSE_WRITE
+                | IN_MOVED_FROM
+                | IN_MOVED_TO
+                | IN_CREATE
+                | IN_DELETE;
+            for dir in dirs {
+                let path = CString::new(dir.as_os_str().as_bytes())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                // SAFETY: path is a valid NUL terminated string which outlives the call.
+                if unsafe { inotify_add_watch(fd, path.as_ptr(), mask) } < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(inotify)
+        }
+
+        /// Reads every pending event without blocking, returning whether there were any.
+        pub fn changed(&mut self) -> io::Result<bool> {
+            let mut buffer = [0; 4096];
+            let mut changed = false;
+            loop {
+                match self.file.read(&mut buffer) {
+                    Ok(0) => return Ok(changed),
+                    Ok(_) => changed = true,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(changed),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+}
+
+/// The modification time and size of every file in the watched directories.
+#[derive(Debug, PartialEq)]
+pub struct Snapshot(BTreeMap<PathBuf, (Option<SystemTime>, u64)>);
+
+impl Snapshot {
+    fn take(dirs: &[PathBuf]) -> Self {
+        let mut files = BTreeMap::new();
+        for dir in dirs {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.filter_map(Result::ok) {
+                if let Ok(metadata) = entry.metadata() {
+                    if metadata.is_file() {
+                        files.insert(entry.path(), (metadata.modified().ok(), metadata.len()));
+                    }
+                }
+            }
+        }
+        Snapshot(files)
+    }
+}
+
+/// Notices changes to the files in a set of directories.
+pub enum Watcher {
+    #[cfg(target_os = "linux")]
+    Notify(inotify::Inotify),
+    Poll {
+        dirs: Vec<PathBuf>,
+        snapshot: Snapshot,
+    },
+}
+
+impl Watcher {
+    /// Watches the directories with file notifications where they are available, otherwise by polling.
+    pub fn new(dirs: Vec<PathBuf>, poll: bool) -> Self {
+        #[cfg(target_os = "linux")]
+        if !poll {
+            let paths: Vec<&Path> = dirs.iter().map(PathBuf::as_path).collect();
+            match inotify::Inotify::new(&paths) {
+                Ok(inotify) => return Watcher::Notify(inotify),
+                Err(e) => eprintln!(
+                    "File notifications are unavailable ({}), polling instead.",
+                    e
+                ),
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = poll;
+
+        Watcher::Poll {
+            snapshot: Snapshot::take(&dirs),
+            dirs,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            #[cfg(target_os = "linux")]
+            Watcher::Notify(_) => "file notifications",
+            Watcher::Poll { .. } => "polling",
+        }
+    }
+
+    /// Returns whether anything changed since the last call, without blocking.
+    pub fn changed(&mut self) -> io::Result<bool> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Watcher::Notify(inotify) => inotify.changed(),
+            Watcher::Poll { dirs, snapshot } => {
+                let current = Snapshot::take(dirs);
+                let changed = current != *snapshot;
+                *snapshot = current;
+                Ok(changed)
+            }
+        }
+    }
+
+    /// Blocks until something changes and the changes have settled.
+    pub fn wait(&mut self) -> io::Result<()> {
+        let interval = match self {
+            Watcher::Poll { .. } => POLL_INTERVAL,
+            #[cfg(target_os = "linux")]
+            Watcher::Notify(_) => SETTLE,
+        };
+        while !self.changed()? {
+            thread::sleep(interval);
+        }
+        loop {
+            thread::sleep(SETTLE);
+            if !self.changed()? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The directories to watch for one day and input: the day's directory, holding its inputs and answers,
+/// and the directory of the input file when it lives somewhere else.
+fn watched_dirs(day: &Day, input: &str) -> Result<Vec<PathBuf>, Box<dyn error::Error>> {
+    let day_dir = input::input_dir().join(day.directory());
+    let mut dirs = vec![day_dir.clone()];
+    match Source::resolve(day, input) {
+        Source::Stdin => return Err("Standard input can't be watched, give an input file.".into()),
+        Source::File(path) => {
+            let dir = match path.parent() {
+                Some(dir) if dir != Path::new("") => dir.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            if dir != day_dir && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    Ok(dirs)
+}
+
+/// Runs the day and prints each answer next to its verdict against the recorded answers.
+fn run_and_report(day: &Day, input: &str, parts: &[Part]) {
+    println!("== day {:02}, input {} ==", day.number, input);
+    let expected = match Answers::load(day) {
+        Ok(answers) => answers.get(input).cloned().unwrap_or_default(),
+        Err(e) => {
+            println!("cannot load the answers: {}", e);
+            Default::default()
+        }
+    };
+
+    let run = runner::quiet_panics(|| runner::run_day(day, input, parts));
+    if let Err(e) = &run.parse {
+        println!("FAILED: {}", e);
+        return;
+    }
+    for part_run in &run.parts {
+        let result = match &part_run.answer {
+            Ok(answer) => PartAnswer(part_run.part, answer).to_string(),
+            Err(e) => format!("{}: FAILED: {}", part_run.part, e),
+        };
+        let verdict = match Verdict::new(expected.part(part_run.part), Some(&part_run.answer)) {
+            Verdict::Pass => String::from("pass"),
+            Verdict::Fail { expected, .. } => format!("FAIL, expected {}", expected),
+            Verdict::Missing { .. } => String::from("no answer recorded"),
+        };
+        println!("{}  ({}, {:.2?})", result, verdict, part_run.time);
+    }
+}
+
+/// Runs the day, then runs it again every time its inputs or answers change. Only returns on error.
+pub fn watch(
+    day: &Day,
+    input: &str,
+    parts: &[Part],
+    poll: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let dirs = watched_dirs(day, input)?;
+    let mut watcher = Watcher::new(dirs.clone(), poll);
+    let names: Vec<String> = dirs.iter().map(|d| d.display().to_string()).collect();
+    println!("Watching {} with {}.", names.join(", "), watcher.kind());
+
+    loop {
+        run_and_report(day, input, parts);
+        watcher.wait()?;
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::input_dir;
+
+    /// A fresh, empty directory for one test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc2023-{}-{}", name, std::process::id()));
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn notices_changes(name: &str, poll: bool) {
+        let dir = scratch_dir(name);
+        std::fs::write(dir.join("input"), "1").unwrap();
+        let mut watcher = Watcher::new(vec![dir.clone()], poll);
+        assert!(!watcher.changed().unwrap());
+
+        std::fs::write(dir.join("input"), "12").unwrap();
+        assert!(watcher.changed().unwrap());
+        assert!(!watcher.changed().unwrap());
+
+        std::fs::write(dir.join("answers"), "input 1 2").unwrap();
+        assert!(watcher.changed().unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn polling_notices_changes() {
+        notices_changes("poll", true);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn notifications_notice_changes() {
+        notices_changes("notify", false);
+    }
+
+    #[test]
+    fn watched_directories() {
+        let day = &crate::day05::DAY;
+        assert_eq!(
+            watched_dirs(day, "01").unwrap(),
+            vec![input_dir().join("05")]
+        );
+        assert_eq!(
+            watched_dirs(day, "/tmp/puzzle").unwrap(),
+            vec![input_dir().join("05"), PathBuf::from("/tmp")]
+        );
+        assert!(watched_dirs(day, "-").is_err());
+    }
+}